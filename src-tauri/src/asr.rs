@@ -1,6 +1,15 @@
 use sherpa_onnx::sense_voice::{SenseVoiceConfig, SenseVoiceRecognizer};
 use anyhow::Result;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Recognized text and the language SenseVoice detected for it
+pub struct Transcription {
+    pub text: String,
+    pub language: String, // e.g. "zh", "en"; empty if not reported
+    pub audio_ms: u64,    // Length of the recording
+    pub latency_ms: u64,  // Time spent resampling and recognizing
+}
 
 #[derive(Clone)]
 pub struct AsrService {
@@ -18,6 +27,10 @@ impl AsrService {
         self.recognizer.lock().unwrap().is_some()
     }
 
+    pub fn unload_model(&self) {
+        *self.recognizer.lock().unwrap() = None;
+    }
+
     pub fn load_model(&self, model_dir: String, language: String) -> Result<()> {
         let model_path = format!("{}/model.onnx", model_dir);
         let tokens_path = format!("{}/tokens.txt", model_dir);
@@ -38,7 +51,9 @@ impl AsrService {
         Ok(())
     }
 
-    pub fn transcribe(&self, samples: Vec<f32>, sample_rate: u32) -> Result<String> {
+    pub fn transcribe(&self, samples: Vec<f32>, sample_rate: u32) -> Result<Transcription> {
+        let started = Instant::now();
+        let audio_ms = if sample_rate > 0 { samples.len() as u64 * 1000 / sample_rate as u64 } else { 0 };
         let mut guard = self.recognizer.lock().unwrap();
         if let Some(recognizer) = guard.as_mut() {
             // SenseVoice expects 16kHz. Resample if needed.
@@ -52,7 +67,14 @@ impl AsrService {
             };
             
             let result = recognizer.transcribe(target_rate, &resampled);
-            Ok(result.text)
+            // SenseVoice reports the language as a token like "<|zh|>"
+            let language = result.lang.trim_start_matches("<|").trim_end_matches("|>").to_string();
+            Ok(Transcription {
+                text: result.text,
+                language,
+                audio_ms,
+                latency_ms: started.elapsed().as_millis() as u64,
+            })
         } else {
             Err(anyhow::anyhow!("Model not loaded"))
         }
@@ -0,0 +1,50 @@
+use serde_json::Value;
+use std::path::Path;
+
+/// Current config schema version. Bump it and append a migration for every change that
+/// serde defaults can't absorb (renamed or moved fields, changed meanings).
+pub const CONFIG_VERSION: u32 = 1;
+
+/// What migrations may need to know about the installation
+pub struct MigrationContext<'a> {
+    pub local_data_dir: &'a Path,
+}
+
+type Migration = fn(&mut Value, &MigrationContext);
+
+/// MIGRATIONS[n] upgrades a version n config to version n + 1
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [relocate_model_dir];
+
+/// Schema version of a raw config; configs from before versioning are version 0
+pub fn config_version(config: &Value) -> u32 {
+    config
+        .get("config_version")
+        .and_then(|v| v.as_u64())
+        .map_or(0, |v| v as u32)
+}
+
+/// Upgrade a raw config to CONFIG_VERSION. Configs from a newer version are left untouched.
+pub fn migrate(config: &mut Value, context: &MigrationContext) {
+    let version = config_version(config);
+    if version >= CONFIG_VERSION || !config.is_object() {
+        return;
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(config, context);
+    }
+    config["config_version"] = Value::from(CONFIG_VERSION);
+}
+
+/// v0 -> v1: keep models in the local data dir instead of a path relative to the working
+/// directory (which caused rebuild loops in dev mode) or the roaming profile
+fn relocate_model_dir(config: &mut Value, context: &MigrationContext) {
+    let relocate = match config.get("model_dir").and_then(|d| d.as_str()) {
+        Some(dir) => dir == "./models/sense-voice" || dir.contains("AppData\\Roaming"),
+        None => true,
+    };
+    if relocate {
+        let model_path = context.local_data_dir.join("models").join("sense-voice");
+        config["model_dir"] = Value::from(model_path.to_string_lossy().to_string());
+    }
+}
@@ -0,0 +1,169 @@
+use crate::script::Script;
+use crate::storage::GuardrailConfig;
+
+/// Below this many significant characters the edit ratio is too noisy to judge
+const MIN_CHARS_FOR_EDIT_RATIO: usize = 4;
+
+/// The script most letters of the text are written in, if any
+fn dominant_script(chars: &[char]) -> Option<Script> {
+    let mut counts: Vec<(Script, usize)> = Vec::new();
+    for script in chars.iter().map(|&c| Script::of(c)).filter(|s| *s != Script::Other) {
+        match counts.iter_mut().find(|(s, _)| *s == script) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((script, 1)),
+        }
+    }
+    counts.into_iter().max_by_key(|(_, n)| *n).map(|(s, _)| s)
+}
+
+/// Letters and digits only, lowercased: punctuation and spacing fixes are always acceptable
+fn significant_chars(text: &str) -> Vec<char> {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Character-level Levenshtein distance
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// Check that an LLM correction stays close to the recognized text.
+/// Returns the rejection reason when the correction looks like a rewrite or an answer.
+pub fn check_correction(raw: &str, corrected: &str, config: &GuardrailConfig) -> Option<String> {
+    if !config.enabled {
+        return None;
+    }
+
+    let raw_chars = significant_chars(raw);
+    let corrected_chars = significant_chars(corrected);
+    if raw_chars.is_empty() {
+        return None;
+    }
+
+    // Checked first: lengths aren't comparable across scripts
+    if config.reject_script_change {
+        if let (Some(before), Some(after)) = (dominant_script(&raw_chars), dominant_script(&corrected_chars)) {
+            if before != after {
+                return Some(format!("script changed from {:?} to {:?}", before, after));
+            }
+        }
+    }
+
+    let length_ratio = corrected_chars.len() as f64 / raw_chars.len() as f64;
+    if length_ratio < config.min_length_ratio || length_ratio > config.max_length_ratio {
+        return Some(format!(
+            "length ratio {:.2} outside [{:.2}, {:.2}]",
+            length_ratio, config.min_length_ratio, config.max_length_ratio
+        ));
+    }
+
+    if raw_chars.len() >= MIN_CHARS_FOR_EDIT_RATIO {
+        let distance = edit_distance(&raw_chars, &corrected_chars);
+        let edit_ratio = distance as f64 / raw_chars.len().max(corrected_chars.len()) as f64;
+        if edit_ratio > config.max_edit_ratio {
+            return Some(format!("edit ratio {:.2} exceeds {:.2}", edit_ratio, config.max_edit_ratio));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(raw: &str, corrected: &str) -> Option<String> {
+        check_correction(raw, corrected, &GuardrailConfig::default())
+    }
+
+    #[test]
+    fn accepts_small_corrections() {
+        assert_eq!(check("我门明天早上九点开会", "我们明天早上九点开会。"), None);
+        assert_eq!(check("their going to the park", "They're going to the park."), None);
+    }
+
+    #[test]
+    fn ignores_punctuation_case_and_spacing() {
+        assert_eq!(check("hello world", "Hello, World!"), None);
+        assert_eq!(check("你好 世界", "你好，世界。"), None);
+    }
+
+    #[test]
+    fn accepts_anything_when_disabled() {
+        let config = GuardrailConfig { enabled: false, ..GuardrailConfig::default() };
+        assert_eq!(check_correction("我门明天开会", "Sure! Here is the corrected text.", &config), None);
+    }
+
+    #[test]
+    fn rejects_rewrites_above_the_edit_ratio() {
+        let reason = check("turn on the lights", "switch off all lamps").unwrap();
+        assert!(reason.starts_with("edit ratio"), "{}", reason);
+    }
+
+    #[test]
+    fn edit_ratio_threshold_is_inclusive() {
+        // 2 of 4 characters changed: exactly 0.5
+        let config = GuardrailConfig { max_edit_ratio: 0.5, ..GuardrailConfig::default() };
+        assert_eq!(check_correction("abcd", "abxy", &config), None);
+        let config = GuardrailConfig { max_edit_ratio: 0.49, ..GuardrailConfig::default() };
+        assert!(check_correction("abcd", "abxy", &config).is_some());
+    }
+
+    #[test]
+    fn skips_edit_ratio_for_short_text() {
+        assert_eq!(check("abc", "xyz"), None);
+    }
+
+    #[test]
+    fn rejects_answers_that_are_too_long_or_short() {
+        let reason = check("明天开会", "明天开会，请大家提前十分钟到场").unwrap();
+        assert!(reason.starts_with("length ratio"), "{}", reason);
+        let reason = check("please send the report by friday", "report").unwrap();
+        assert!(reason.starts_with("length ratio"), "{}", reason);
+    }
+
+    #[test]
+    fn length_ratio_bounds_are_inclusive() {
+        // 5 -> 8 characters is exactly the default maximum of 1.6
+        assert_eq!(check("abcde", "abcdexyz"), None);
+        assert!(check("abcde", "abcdexyzw").is_some());
+        // 10 -> 5 characters is exactly the default minimum of 0.5
+        let config = GuardrailConfig { max_edit_ratio: 1.0, ..GuardrailConfig::default() };
+        assert_eq!(check_correction("abcdefghij", "abcde", &config), None);
+        assert!(check_correction("abcdefghij", "abcd", &config).is_some());
+    }
+
+    #[test]
+    fn rejects_script_change() {
+        let reason = check("明天早上九点开会", "Meeting at nine tomorrow").unwrap();
+        assert!(reason.starts_with("script changed"), "{}", reason);
+    }
+
+    #[test]
+    fn script_change_can_be_allowed() {
+        let config = GuardrailConfig {
+            reject_script_change: false,
+            max_edit_ratio: 1.0,
+            max_length_ratio: 10.0,
+            ..GuardrailConfig::default()
+        };
+        assert_eq!(check_correction("明天早上九点开会", "Meeting at nine tomorrow", &config), None);
+    }
+
+    #[test]
+    fn mixed_script_text_keeps_its_dominant_script() {
+        assert_eq!(check("我们用 Rust 写的这个程序很快", "我们用 Rust 写的这个程序很快。"), None);
+    }
+}
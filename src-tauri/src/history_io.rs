@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use crate::storage::{HistoryItem, HistoryRange};
+
+/// File formats history can be exported to
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Csv,
+    Json,
+    Jsonl,
+    Text,
+}
+
+const CSV_HEADER: &str = "id,timestamp,text,language,original_text,translated_to,llm_model,tags,duration_ms";
+
+/// Whether an item's text (or the text it was translated from) contains `search`, ignoring case
+fn matches_search(item: &HistoryItem, search: &str) -> bool {
+    let search = search.to_lowercase();
+    item.text.to_lowercase().contains(&search)
+        || item.original_text.as_deref().is_some_and(|text| text.to_lowercase().contains(&search))
+}
+
+/// History items in range that match the search text, oldest first
+fn select_items<'a>(history: &'a [HistoryItem], range: &HistoryRange, search: Option<&str>) -> Vec<&'a HistoryItem> {
+    let search = search.map(str::trim).filter(|s| !s.is_empty());
+    let mut items: Vec<_> = history
+        .iter()
+        .filter(|item| range.contains(item) && search.is_none_or(|search| matches_search(item, search)))
+        .collect();
+    items.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    items
+}
+
+/// Quote a CSV field, and defuse values a spreadsheet would run as a formula
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn to_csv(items: &[&HistoryItem]) -> String {
+    let mut out = format!("{}\n", CSV_HEADER);
+    for item in items {
+        let tags = item.tags.join(";");
+        let fields = [
+            item.id.as_str(),
+            item.timestamp.as_str(),
+            item.text.as_str(),
+            item.language.as_str(),
+            item.original_text.as_deref().unwrap_or_default(),
+            item.translated_to.as_deref().unwrap_or_default(),
+            item.llm_model.as_deref().unwrap_or_default(),
+            tags.as_str(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&format!("{},{}\n", row.join(","), item.duration_ms));
+    }
+    out
+}
+
+/// One section per day, one bullet per item; continuation lines are indented to stay in the bullet
+fn to_markdown(items: &[&HistoryItem]) -> String {
+    let mut out = String::from("# Dictation history\n");
+    let mut current_day = "";
+    for item in items {
+        let (day, time) = item.timestamp.split_once(' ').unwrap_or((&item.timestamp, ""));
+        if day != current_day {
+            out.push_str(&format!("\n## {}\n\n", day));
+            current_day = day;
+        }
+        out.push_str(&format!("- **{}** {}\n", time, item.text.trim().replace('\n', "\n  ")));
+    }
+    out
+}
+
+fn to_text(items: &[&HistoryItem]) -> String {
+    items
+        .iter()
+        .map(|item| format!("[{}] {}\n", item.timestamp, item.text.trim()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render(items: &[&HistoryItem], format: ExportFormat) -> Result<String> {
+    Ok(match format {
+        ExportFormat::Markdown => to_markdown(items),
+        ExportFormat::Csv => to_csv(items),
+        ExportFormat::Json => serde_json::to_string_pretty(items)?,
+        ExportFormat::Jsonl => items
+            .iter()
+            .map(|item| serde_json::to_string(item).map(|line| line + "\n"))
+            .collect::<Result<String, _>>()?,
+        ExportFormat::Text => to_text(items),
+    })
+}
+
+/// Write the selected history items to `path`; returns how many were exported
+pub fn export_history(
+    history: &[HistoryItem],
+    format: ExportFormat,
+    range: &HistoryRange,
+    search: Option<&str>,
+    path: &Path,
+) -> Result<usize> {
+    let items = select_items(history, range, search);
+    fs::write(path, render(&items, format)?)?;
+    Ok(items.len())
+}
+
+/// Outcome of merging an imported history into the local one
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub skipped: usize, // Already in the local history
+    pub pruned: usize,  // Removed afterwards by the retention settings
+}
+
+/// Read history items from a JSON array (an export or another machine's history.json) or JSON Lines
+pub fn read_history_file(path: &Path) -> Result<Vec<HistoryItem>> {
+    let content = fs::read_to_string(path)?;
+    if content.trim_start().starts_with('[') {
+        return serde_json::from_str(&content).context("Not a valid history file");
+    }
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| serde_json::from_str(line).with_context(|| format!("Invalid history item on line {}", index + 1)))
+        .collect()
+}
+
+/// Add the imported items that aren't in `history` yet, matching by id or by text and timestamp,
+/// and keep the history newest first
+pub fn merge_history(history: &mut Vec<HistoryItem>, imported: Vec<HistoryItem>) -> ImportSummary {
+    let mut ids: HashSet<String> = history.iter().map(|item| item.id.clone()).collect();
+    let mut entries: HashSet<(String, String)> =
+        history.iter().map(|item| (item.text.clone(), item.timestamp.clone())).collect();
+    let mut summary = ImportSummary::default();
+
+    for item in imported {
+        let entry = (item.text.clone(), item.timestamp.clone());
+        if ids.contains(&item.id) || entries.contains(&entry) {
+            summary.skipped += 1;
+            continue;
+        }
+        ids.insert(item.id.clone());
+        entries.insert(entry);
+        history.push(item);
+        summary.added += 1;
+    }
+
+    if summary.added > 0 {
+        history.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    }
+    summary
+}
@@ -3,11 +3,20 @@ use std::thread;
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, atomic::{AtomicBool, Ordering}, Mutex};
 
+/// Which trigger produced an event
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trigger {
+    Mouse,  // Middle button
+    Hold,   // Left Ctrl + Left Win
+    Toggle, // Right Alt
+}
+
 #[derive(Debug, Clone)]
 pub enum InputEvent {
-    Start,
-    Stop,
+    Start(Trigger),
+    Stop(Trigger),
     Toggle,
+    NextProfile, // Left Ctrl + Left Alt + P
     MouseMove { x: f64, y: f64 },
 }
 
@@ -50,6 +59,7 @@ impl InputListener {
         thread::spawn(move || {
             let mut is_ctrl = false;
             let mut is_win = false;
+            let mut is_alt = false;
             let mut combo_active = false;
 
             if let Err(error) = listen(move |event| {
@@ -57,12 +67,12 @@ impl InputListener {
                     // Mouse Mode
                     EventType::ButtonPress(Button::Middle) => {
                         if enable_mouse.load(Ordering::Relaxed) {
-                            tx.send(InputEvent::Start).ok();
+                            tx.send(InputEvent::Start(Trigger::Mouse)).ok();
                         }
                     },
                     EventType::ButtonRelease(Button::Middle) => {
                         if enable_mouse.load(Ordering::Relaxed) {
-                            tx.send(InputEvent::Stop).ok();
+                            tx.send(InputEvent::Stop(Trigger::Mouse)).ok();
                         }
                     },
                     
@@ -91,6 +101,15 @@ impl InputListener {
                         check_combo(&enable_hold, &mut combo_active, is_ctrl, is_win, &tx);
                     },
 
+                    // Switch to the next settings profile (Left Ctrl + Left Alt + P)
+                    EventType::KeyPress(Key::Alt) => is_alt = true,
+                    EventType::KeyRelease(Key::Alt) => is_alt = false,
+                    EventType::KeyPress(Key::KeyP) => {
+                        if is_ctrl && is_alt {
+                            tx.send(InputEvent::NextProfile).ok();
+                        }
+                    },
+
                     // Mouse Position Tracking
                     EventType::MouseMove { x, y } => {
                         // 始终更新最新的鼠标位置
@@ -120,9 +139,9 @@ fn check_combo(enable_hold: &Arc<AtomicBool>, active: &mut bool, ctrl: bool, win
     let is_combo = ctrl && win;
     if is_combo && !*active {
         *active = true;
-        tx.send(InputEvent::Start).ok();
+        tx.send(InputEvent::Start(Trigger::Hold)).ok();
     } else if !is_combo && *active {
         *active = false;
-        tx.send(InputEvent::Stop).ok();
+        tx.send(InputEvent::Stop(Trigger::Hold)).ok();
     }
 }
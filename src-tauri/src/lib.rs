@@ -1,14 +1,22 @@
 mod asr;
 mod audio;
+mod config_migration;
+mod guardrail;
+mod history_io;
 mod http_client;
 mod input_listener;
 mod llm;
+mod llm_provider;
 mod model_manager;
+mod model_update;
+mod script;
+mod secrets;
+mod stats;
 mod storage;
 
 use std::sync::Mutex;
 use tauri::{AppHandle, Emitter, Manager, Runtime, WebviewUrl, WebviewWindowBuilder};
-use storage::{AppConfig, HistoryItem, LlmConfig, ModelVersion, ProxyConfig};
+use storage::{AppConfig, HistoryItem, LlmConfig, ModelVersion, ProxyConfig, TriggerLlmMode};
 use serde::Serialize;
 use tokio_util::sync::CancellationToken;
 
@@ -44,6 +52,9 @@ fn preview_text(s: &str, max_chars: usize) -> String {
     out
 }
 
+// Delay before the first background model update check after startup
+const MODEL_UPDATE_INITIAL_DELAY_SECS: u64 = 60;
+
 // Indicator window colors
 const INDICATOR_COLOR_RECORDING: &str = "#4f9d9a"; // Indigo-cyan for normal recording
 const INDICATOR_COLOR_LLM: &str = "#dc2626"; // Red for LLM processing
@@ -98,10 +109,12 @@ fn move_indicator_window<R: Runtime>(app_handle: &AppHandle<R>, x: f64, y: f64)
 /// Process transcribed text: apply LLM correction if enabled, save to history, emit event, paste
 fn process_transcription<R: Runtime>(
     app_handle: &AppHandle<R>,
-    text: String,
+    transcription: asr::Transcription,
     processing: ProcessingState,
     seq_id: u64,
+    trigger: input_listener::Trigger,
 ) {
+    let asr::Transcription { text, language, audio_ms, latency_ms } = transcription;
     if text.trim().is_empty() {
         println!("[TRANSCRIPTION] #{} empty, skipping", seq_id);
         processing.store(false, std::sync::atomic::Ordering::SeqCst);
@@ -117,8 +130,25 @@ fn process_transcription<R: Runtime>(
 
     let storage = app_handle.state::<StorageState>();
     let config = storage.load_config();
-    let llm_config = config.llm_config.clone();
+    let modes = &config.llm_config.trigger_modes;
+    let mode = match trigger {
+        input_listener::Trigger::Mouse => &modes.mouse,
+        input_listener::Trigger::Hold => &modes.hold,
+        input_listener::Trigger::Toggle => &modes.toggle,
+    };
+    let llm_config = llm::config_for_mode(&storage.resolve_llm_config(&config.llm_config), mode, &language)
+        .filter(llm::is_configured);
+    let translate_to = (*mode == TriggerLlmMode::Translate
+        && llm::should_translate(&config.llm_config.translation, &language))
+        .then(|| config.llm_config.translation.target_language.clone());
+    let recent_dictation = match &llm_config {
+        Some(llm_config) if llm_config.context.include_history => {
+            llm::recent_dictation(&storage.load_history(), &llm_config.context)
+        }
+        _ => Vec::new(),
+    };
     let proxy_config = config.proxy.clone();
+    let incognito = config.history.incognito;
 
     let app_handle_clone = app_handle.clone();
     let processing_clone = processing.clone();
@@ -134,7 +164,18 @@ fn process_transcription<R: Runtime>(
         }
         let _guard = ProcessingGuard(processing_clone);
 
-        let final_text = if llm_config.enabled && !llm_config.api_key.is_empty() {
+        // Set when the text was already typed progressively during LLM streaming
+        let mut already_typed = false;
+        // Why the LLM correction was discarded, if it was
+        let mut llm_rejection: Option<String> = None;
+        // The recognized text, when the final text is a translation of it
+        let mut original_text: Option<String> = None;
+        // Model, token usage and failure of the LLM request, for usage stats
+        let mut llm_model: Option<String> = None;
+        let mut llm_usage = None;
+        let mut llm_error: Option<String> = None;
+
+        let final_text = if let Some(llm_config) = llm_config {
             app_handle_clone.emit("llm_processing", true).ok();
             {
                 let listener = app_handle_clone.state::<InputListenerState>();
@@ -142,14 +183,59 @@ fn process_transcription<R: Runtime>(
             }
             show_indicator_window(&app_handle_clone, true);
 
-            let result = match llm::correct_text(&text, &llm_config, &proxy_config).await {
-                Ok(corrected) => corrected,
+            // With progressive typing the correction is typed while it streams in
+            // and reconciled with the final (validated) text afterwards
+            let mut typer = (llm_config.stream && llm_config.progressive_typing)
+                .then(|| ProgressiveTyper::start(seq_id));
+
+            let corrected = if llm_config.stream {
+                llm::correct_text_streaming(&text, &recent_dictation, &llm_config, &proxy_config, |delta| {
+                    if let Some(typer) = typer.as_mut() {
+                        typer.type_text(delta);
+                    }
+                }).await
+            } else {
+                llm::correct_text(&text, &recent_dictation, &llm_config, &proxy_config).await
+            };
+
+            let result = match corrected {
+                Ok(correction) => {
+                    llm_model = Some(correction.model);
+                    llm_usage = correction.usage;
+                    match guardrail::check_correction(&text, &correction.text, &llm_config.guardrail) {
+                        None => {
+                            if translate_to.is_some() {
+                                original_text = Some(text);
+                            }
+                            correction.text
+                        }
+                        Some(reason) => {
+                            eprintln!(
+                                "[TRANSCRIPTION] #{} LLM correction rejected ({}), using original text: '{}'",
+                                seq_id,
+                                reason,
+                                preview_text(&correction.text, 80)
+                            );
+                            llm_rejection = Some(reason);
+                            text
+                        }
+                    }
+                }
                 Err(e) => {
                     eprintln!("LLM correction failed, using original text: {}", e);
+                    llm_model = Some(llm_config.model.clone());
+                    llm_error = Some(e.to_string());
                     text
                 }
             };
 
+            if let Some(typer) = typer {
+                // finish joins the typing thread, so it must not block an async worker
+                let final_text = result.clone();
+                tauri::async_runtime::spawn_blocking(move || typer.finish(&final_text)).await.ok();
+                already_typed = true;
+            }
+
             app_handle_clone.emit("llm_processing", false).ok();
             {
                 let listener = app_handle_clone.state::<InputListenerState>();
@@ -171,12 +257,29 @@ fn process_transcription<R: Runtime>(
             id: uuid::Uuid::new_v4().to_string(),
             timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
             text: final_text.clone(),
-            duration_ms: 0,
+            duration_ms: audio_ms,
+            asr_latency_ms: latency_ms,
+            llm_rejection,
+            language,
+            translated_to: original_text.as_ref().and(translate_to),
+            original_text,
+            llm_model,
+            llm_usage,
+            llm_error,
+            edited_from: None,
+            pinned: false,
+            tags: Vec::new(),
         };
-        let storage = app_handle_clone.state::<StorageState>();
-        storage.add_history_item(item.clone()).ok();
+        if !incognito {
+            let storage = app_handle_clone.state::<StorageState>();
+            storage.add_history_item(item.clone()).ok();
+        }
         app_handle_clone.emit("transcription_update", item).ok();
 
+        if already_typed {
+            return;
+        }
+
         // Output text (blocking, on a dedicated thread to not block tokio)
         let text_to_paste = final_text;
         let id = seq_id;
@@ -212,6 +315,78 @@ fn output_text(text: &str, seq_id: u64) {
     println!("[OUTPUT] #{} done", seq_id);
 }
 
+enum TyperCommand {
+    Text(String),
+    Backspace(usize),
+}
+
+/// 流式输出：在专用线程中逐段输入 LLM 返回的文本
+/// Keeps track of what was typed so the final correction can be reconciled with it
+struct ProgressiveTyper {
+    tx: std::sync::mpsc::Sender<TyperCommand>,
+    thread: std::thread::JoinHandle<()>,
+    typed: String,
+}
+
+impl ProgressiveTyper {
+    fn start(seq_id: u64) -> Self {
+        let (tx, rx) = std::sync::mpsc::channel::<TyperCommand>();
+        let thread = std::thread::spawn(move || {
+            println!("[OUTPUT] #{} progressive start", seq_id);
+
+            // 与 output_text 一致，等待目标窗口处理完触发事件
+            std::thread::sleep(std::time::Duration::from_millis(80));
+
+            let mut enigo = match Enigo::new(&Settings::default()) {
+                Ok(e) => e,
+                Err(e) => {
+                    eprintln!("[OUTPUT] #{} enigo init failed: {:?}", seq_id, e);
+                    return;
+                }
+            };
+
+            for command in rx {
+                let res = match command {
+                    TyperCommand::Text(text) => enigo.text(&text),
+                    TyperCommand::Backspace(count) => (0..count)
+                        .try_for_each(|_| enigo.key(enigo::Key::Backspace, enigo::Direction::Click)),
+                };
+                if let Err(e) = res {
+                    eprintln!("[OUTPUT] #{} progressive input failed: {:?}", seq_id, e);
+                }
+            }
+
+            println!("[OUTPUT] #{} progressive done", seq_id);
+        });
+
+        Self { tx, thread, typed: String::new() }
+    }
+
+    fn type_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.typed.push_str(text);
+        self.tx.send(TyperCommand::Text(text.to_string())).ok();
+    }
+
+    /// Make the typed output match final_text, erasing the streamed text if it diverged
+    /// (e.g. the stream failed midway and we fall back to the raw ASR text), then wait for typing to finish
+    fn finish(mut self, final_text: &str) {
+        if let Some(rest) = final_text.strip_prefix(self.typed.as_str()) {
+            let rest = rest.to_string();
+            self.type_text(&rest);
+        } else {
+            let count = self.typed.chars().count();
+            self.tx.send(TyperCommand::Backspace(count)).ok();
+            self.typed.clear();
+            self.type_text(final_text);
+        }
+        drop(self.tx);
+        self.thread.join().ok();
+    }
+}
+
 #[derive(Serialize)]
 pub struct ModelVersionsStatus {
     quantized: bool,
@@ -230,6 +405,12 @@ fn get_config(state: tauri::State<StorageState>) -> AppConfig {
     state.load_config()
 }
 
+/// Why config.json couldn't be loaded at startup, if it couldn't (defaults are in use then)
+#[tauri::command]
+fn get_config_error(state: tauri::State<StorageState>) -> Option<String> {
+    state.config_error()
+}
+
 #[tauri::command]
 fn save_config(
     state: tauri::State<StorageState>, 
@@ -244,6 +425,131 @@ fn save_config(
     state.save_config(&config).map_err(|e| e.to_string())
 }
 
+/// Change only the given settings (a partial config); returns the saved config
+#[tauri::command]
+fn patch_config(
+    state: tauri::State<StorageState>,
+    listener: tauri::State<InputListenerState>,
+    patch: serde_json::Value,
+) -> Result<AppConfig, String> {
+    let config = state.patch_config(patch).map_err(|e| format!("{:#}", e))?;
+
+    // Update listener flags immediately (hot-reload)
+    listener.enable_mouse.store(config.trigger_mouse, std::sync::atomic::Ordering::Relaxed);
+    listener.enable_hold.store(config.trigger_hold, std::sync::atomic::Ordering::Relaxed);
+    listener.enable_toggle.store(config.trigger_toggle, std::sync::atomic::Ordering::Relaxed);
+
+    Ok(config)
+}
+
+/// Apply the settings running services hold on to after the config changed underneath them:
+/// trigger flags, the input device and the loaded model. LLM and proxy settings are read per request.
+fn apply_config_to_services<R: Runtime>(app: &AppHandle<R>, previous: &AppConfig, config: &AppConfig) {
+    let listener = app.state::<InputListenerState>();
+    listener.enable_mouse.store(config.trigger_mouse, std::sync::atomic::Ordering::Relaxed);
+    listener.enable_hold.store(config.trigger_hold, std::sync::atomic::Ordering::Relaxed);
+    listener.enable_toggle.store(config.trigger_toggle, std::sync::atomic::Ordering::Relaxed);
+
+    if config.input_device != previous.input_device {
+        if let Ok(mut audio) = app.state::<AudioState>().lock() {
+            if let Err(e) = audio.init_with_device(&config.input_device, app.clone()) {
+                eprintln!("[PROFILE] Failed to switch input device: {}", e);
+            }
+        }
+    }
+
+    let model_changed = config.model_version != previous.model_version || config.language != previous.language;
+    if !model_changed {
+        return;
+    }
+    if !model_manager::check_model_exists_for_version(&config.model_dir, &config.model_version) {
+        // Keep the loaded model in step with the selected version so delete_model's check stays right
+        app.state::<AsrState>().unload_model();
+        return;
+    }
+    let asr = app.state::<AsrState>().inner().clone();
+    let handle = app.clone();
+    let model_dir = config.model_dir.clone();
+    let model_version = config.model_version.clone();
+    let language = config.language.clone();
+    tauri::async_runtime::spawn(async move {
+        let model_path = model_manager::get_model_dir_for_version(&model_dir, &model_version);
+        match asr.load_model(model_path, language) {
+            Ok(_) => {
+                model_manager::mark_model_used(&model_dir, &model_version);
+                handle.emit("model_loaded", ()).ok();
+            }
+            Err(e) => eprintln!("[PROFILE] Failed to load model: {}", e),
+        }
+    });
+}
+
+/// Switch to a named settings profile and apply it to the running services
+fn activate_profile<R: Runtime>(app: &AppHandle<R>, name: &str) -> Result<(), String> {
+    let storage = app.state::<StorageState>();
+    let previous = storage.load_config();
+    let Some(profile) = previous.profiles.iter().find(|p| p.name == name) else {
+        return Err(format!("Profile {} not found", name));
+    };
+    if !model_manager::check_model_exists_for_version(&previous.model_dir, &profile.model_version) {
+        return Err(format!("The model used by profile {} is not downloaded", name));
+    }
+    let config = storage
+        .update_config(|config| {
+            config.apply_profile(name);
+        })
+        .map_err(|e| e.to_string())?;
+    apply_config_to_services(app, &previous, &config);
+    app.emit("profile_switched", name).ok();
+    Ok(())
+}
+
+/// Save the current settings as a named profile (replacing one with the same name)
+#[tauri::command]
+fn save_profile(name: String, state: tauri::State<StorageState>) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name is empty".to_string());
+    }
+    state.update_config(|config| config.save_profile(name)).map(|_| ()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_profile(name: String, state: tauri::State<StorageState>) -> Result<(), String> {
+    state
+        .update_config(|config| {
+            config.profiles.retain(|p| p.name != name);
+            if config.active_profile.as_deref() == Some(name.as_str()) {
+                config.active_profile = None;
+            }
+        })
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn switch_profile<R: Runtime>(app: AppHandle<R>, name: String) -> Result<(), String> {
+    activate_profile(&app, &name)
+}
+
+#[tauri::command]
+fn export_config(path: String, include_secrets: bool, state: tauri::State<StorageState>) -> Result<(), String> {
+    state
+        .export_config(std::path::Path::new(&path), include_secrets)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn import_config<R: Runtime>(app: AppHandle<R>, path: String) -> Result<AppConfig, String> {
+    let storage = app.state::<StorageState>();
+    let previous = storage.load_config();
+    let config = storage
+        .import_config(std::path::Path::new(&path))
+        .map_err(|e| format!("{:#}", e))?;
+    apply_config_to_services(&app, &previous, &config);
+    Ok(config)
+}
+
 #[tauri::command]
 fn get_history(state: tauri::State<StorageState>) -> Vec<HistoryItem> {
     state.load_history()
@@ -254,6 +560,68 @@ fn clear_history(state: tauri::State<StorageState>) -> Result<(), String> {
     state.clear_history().map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+fn delete_history_item(id: String, state: tauri::State<StorageState>) -> Result<(), String> {
+    state.delete_history_item(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn edit_history_item(id: String, text: String, state: tauri::State<StorageState>) -> Result<(), String> {
+    state.edit_history_item(&id, text).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_history_item_pinned(id: String, pinned: bool, state: tauri::State<StorageState>) -> Result<(), String> {
+    state.set_history_item_pinned(&id, pinned).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_history_item_tags(id: String, tags: Vec<String>, state: tauri::State<StorageState>) -> Result<(), String> {
+    state.set_history_item_tags(&id, tags).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_llm_usage_stats(range: Option<storage::HistoryRange>, state: tauri::State<StorageState>) -> stats::LlmUsageStats {
+    let config = state.load_config();
+    stats::llm_usage_stats(&state.load_history(), &range.unwrap_or_default(), &config.llm_config.model_prices)
+}
+
+#[tauri::command]
+fn get_dictation_stats(range: Option<storage::HistoryRange>, state: tauri::State<StorageState>) -> stats::DictationStats {
+    stats::dictation_stats(&state.load_history(), &range.unwrap_or_default())
+}
+
+#[tauri::command]
+fn export_history(
+    format: history_io::ExportFormat,
+    range: Option<storage::HistoryRange>,
+    search: Option<String>,
+    path: String,
+    state: tauri::State<StorageState>,
+) -> Result<usize, String> {
+    history_io::export_history(
+        &state.load_history(),
+        format,
+        &range.unwrap_or_default(),
+        search.as_deref(),
+        std::path::Path::new(&path),
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn import_history(path: String, state: tauri::State<StorageState>) -> Result<history_io::ImportSummary, String> {
+    let imported = history_io::read_history_file(std::path::Path::new(&path)).map_err(|e| format!("{:#}", e))?;
+    let retention = state.load_config().history;
+    state
+        .update_history(|history| {
+            let mut summary = history_io::merge_history(history, imported);
+            summary.pruned = retention.prune(history);
+            summary
+        })
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn check_model_status(state: tauri::State<'_, StorageState>) -> Result<bool, String> {
     let config = state.load_config();
@@ -288,14 +656,15 @@ async fn get_model_detailed_status(
 async fn download_model<R: Runtime>(app: AppHandle<R>, state: tauri::State<'_, StorageState>) -> Result<(), String> {
     let config = state.load_config();
     let model_dir = config.model_dir.clone();
+    let mirrors = config.model_mirrors.clone();
     let proxy = config.proxy.clone();
 
     // Run download in background
     let handle = app.clone();
     tauri::async_runtime::spawn(async move {
         let progress_handle = handle.clone();
-        let res = model_manager::download_model(&model_dir, &proxy, move |current, total| {
-            progress_handle.emit("download_progress", serde_json::json!({ "current": current, "total": total })).ok();
+        let res = model_manager::download_model(&model_dir, &mirrors, &proxy, move |progress| {
+            progress_handle.emit("download_progress", progress).ok();
         }).await;
         
         if let Err(e) = res {
@@ -308,23 +677,19 @@ async fn download_model<R: Runtime>(app: AppHandle<R>, state: tauri::State<'_, S
     Ok(())
 }
 
-#[tauri::command]
-async fn download_model_for_version<R: Runtime>(
-    app: AppHandle<R>,
-    state: tauri::State<'_, StorageState>,
-    asr: tauri::State<'_, AsrState>,
-    cancel_state: tauri::State<'_, DownloadCancelState>,
-    version: String
+/// Download a model build in the background, then switch the config to it and load it
+fn spawn_model_download<R: Runtime>(
+    app: &AppHandle<R>,
+    asr: &AsrState,
+    cancel_state: &DownloadCancelState,
+    config: &AppConfig,
+    model_version: ModelVersion,
+    build: model_manager::ModelBuild
 ) -> Result<(), String> {
-    let config = state.load_config();
     let model_dir = config.model_dir.clone();
     let language = config.language.clone();
+    let mirrors = config.model_mirrors.clone();
     let proxy = config.proxy.clone();
-    let model_version = match version.as_str() {
-        "quantized" => ModelVersion::Quantized,
-        "unquantized" => ModelVersion::Unquantized,
-        _ => return Err("Invalid version".to_string()),
-    };
 
     // Create cancellation token
     let cancel_token = CancellationToken::new();
@@ -334,18 +699,20 @@ async fn download_model_for_version<R: Runtime>(
     }
 
     let handle = app.clone();
-    let version_for_download = model_version.clone();
-    let asr_clone = asr.inner().clone();
+    let version_for_download = model_version;
+    let asr_clone = asr.clone();
 
     tauri::async_runtime::spawn(async move {
         let progress_handle = handle.clone();
         let res = model_manager::download_model_version(
             &model_dir,
             &version_for_download,
+            &build,
+            &mirrors,
             &proxy,
             cancel_token,
-            move |current, total| {
-                progress_handle.emit("download_progress", serde_json::json!({ "current": current, "total": total })).ok();
+            move |progress| {
+                progress_handle.emit("download_progress", progress).ok();
             }
         ).await;
 
@@ -356,28 +723,25 @@ async fn download_model_for_version<R: Runtime>(
         }
 
         match res {
+            Err(model_manager::DownloadError::Cancelled) => {
+                handle.emit("download_cancelled", ()).ok();
+            }
             Err(e) => {
-                let error_msg = e.to_string();
-                if error_msg.contains("cancelled") {
-                    handle.emit("download_cancelled", ()).ok();
-                } else {
-                    handle.emit("download_error", error_msg).ok();
-                }
+                handle.emit("download_error", e.to_string()).ok();
             }
             Ok(_) => {
-                // Download complete - now auto-load the model
-                handle.emit("download_complete", ()).ok();
-
                 // Update config to use this version (get state from handle)
                 let storage = handle.state::<StorageState>();
-                let mut new_config = storage.load_config();
-                new_config.model_version = version_for_download.clone();
-                let _ = storage.save_config(&new_config);
+                let _ = storage.update_config(|config| config.model_version = version_for_download.clone());
 
-                // Load the model
+                // Load the model; download_complete ends the progress display, so it comes last
+                handle.emit("download_progress", model_manager::DownloadProgress::new(model_manager::DownloadPhase::Loading)).ok();
                 let model_path = model_manager::get_model_dir_for_version(&model_dir, &version_for_download);
-                match asr_clone.load_model(model_path, language) {
+                let loaded = asr_clone.load_model(model_path, language);
+                handle.emit("download_complete", ()).ok();
+                match loaded {
                     Ok(_) => {
+                        model_manager::mark_model_used(&model_dir, &version_for_download);
                         handle.emit("model_loaded", ()).ok();
                     },
                     Err(e) => eprintln!("Failed to auto-load model after download: {}", e),
@@ -389,6 +753,100 @@ async fn download_model_for_version<R: Runtime>(
     Ok(())
 }
 
+#[tauri::command]
+async fn download_model_for_version<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, StorageState>,
+    asr: tauri::State<'_, AsrState>,
+    cancel_state: tauri::State<'_, DownloadCancelState>,
+    version: String
+) -> Result<(), String> {
+    let config = state.load_config();
+    let model_version = match version.as_str() {
+        "quantized" => ModelVersion::Quantized,
+        "unquantized" => ModelVersion::Unquantized,
+        _ => return Err("Invalid version".to_string()),
+    };
+
+    let build = model_manager::get_default_build(&model_version);
+    spawn_model_download(&app, &asr, &cancel_state, &config, model_version, build)
+}
+
+#[tauri::command]
+async fn check_model_update(state: tauri::State<'_, StorageState>) -> Result<Vec<model_update::ModelUpdateInfo>, String> {
+    let config = state.load_config();
+    model_update::check_for_updates(&config.model_dir, &config.model_update, &config.proxy)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Download the newer build of a version after the user confirmed a `model_update_available` event
+#[tauri::command]
+async fn download_model_update<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, StorageState>,
+    asr: tauri::State<'_, AsrState>,
+    cancel_state: tauri::State<'_, DownloadCancelState>,
+    version: String
+) -> Result<(), String> {
+    let config = state.load_config();
+    let model_version = match version.as_str() {
+        "quantized" => ModelVersion::Quantized,
+        "unquantized" => ModelVersion::Unquantized,
+        _ => return Err("Invalid version".to_string()),
+    };
+
+    let build = model_update::get_update_build(&config.model_dir, &model_version, &config.model_update, &config.proxy)
+        .await
+        .map_err(|e| e.to_string())?;
+    spawn_model_download(&app, &asr, &cancel_state, &config, model_version, build)
+}
+
+/// Stop notifying about a build offered by a `model_update_available` event
+#[tauri::command]
+fn dismiss_model_update(state: tauri::State<'_, StorageState>, build: String) -> Result<(), String> {
+    state
+        .update_config(|config| {
+            if !config.model_update.dismissed_builds.contains(&build) {
+                config.model_update.dismissed_builds.push(build);
+            }
+        })
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Periodically check the model catalog and notify the UI about newer builds.
+/// Each build is announced once per run, and never again once dismissed.
+async fn run_model_update_checks<R: Runtime>(app_handle: AppHandle<R>) {
+    // Give startup (model loading, audio init) a head start before the first check
+    tokio::time::sleep(std::time::Duration::from_secs(MODEL_UPDATE_INITIAL_DELAY_SECS)).await;
+
+    let mut notified = std::collections::HashSet::new();
+    loop {
+        // Re-read config every round so toggling the setting applies without restart
+        let config = app_handle.state::<StorageState>().load_config();
+        if config.model_update.enabled {
+            match model_update::check_for_updates(&config.model_dir, &config.model_update, &config.proxy).await {
+                Ok(updates) => {
+                    for update in updates {
+                        if config.model_update.dismissed_builds.contains(&update.latest.name)
+                            || !notified.insert(update.latest.name.clone())
+                        {
+                            continue;
+                        }
+                        println!("[MODEL_UPDATE] {} -> {}", update.current_build, update.latest.name);
+                        app_handle.emit("model_update_available", update).ok();
+                    }
+                }
+                Err(e) => eprintln!("[MODEL_UPDATE] check failed: {}", e),
+            }
+        }
+
+        let hours = config.model_update.interval_hours.max(1);
+        tokio::time::sleep(std::time::Duration::from_secs(hours * 3600)).await;
+    }
+}
+
 #[tauri::command]
 async fn switch_model_version(
     state: tauri::State<'_, StorageState>,
@@ -401,7 +859,7 @@ async fn switch_model_version(
         _ => return Err("Invalid version".to_string()),
     };
     
-    let mut config = state.load_config();
+    let config = state.load_config();
     
     // Check if version is downloaded
     if !model_manager::check_model_exists_for_version(&config.model_dir, &model_version) {
@@ -409,12 +867,14 @@ async fn switch_model_version(
     }
     
     // Update config
-    config.model_version = model_version.clone();
-    state.save_config(&config).map_err(|e| e.to_string())?;
+    let config = state
+        .update_config(|config| config.model_version = model_version.clone())
+        .map_err(|e| e.to_string())?;
     
     // Reload ASR with new model
     let model_path = model_manager::get_model_dir_for_version(&config.model_dir, &model_version);
     asr.load_model(model_path, config.language.clone()).map_err(|e| e.to_string())?;
+    model_manager::mark_model_used(&config.model_dir, &model_version);
     
     Ok(())
 }
@@ -468,14 +928,13 @@ async fn import_model<R: Runtime>(
 
                 // Update config to use this version - get state from app handle
                 let storage = handle.state::<StorageState>();
-                let mut new_config = storage.load_config();
-                new_config.model_version = version_for_import.clone();
-                let _ = storage.save_config(&new_config);
+                let _ = storage.update_config(|config| config.model_version = version_for_import.clone());
 
                 // Load the model
                 let model_path = model_manager::get_model_dir_for_version(&model_dir, &version_for_import);
                 match asr_clone.load_model(model_path, language) {
                     Ok(_) => {
+                        model_manager::mark_model_used(&model_dir, &version_for_import);
                         handle.emit("model_loaded", ()).ok();
                     },
                     Err(e) => eprintln!("Failed to auto-load model after import: {}", e),
@@ -487,6 +946,39 @@ async fn import_model<R: Runtime>(
     Ok(())
 }
 
+#[tauri::command]
+async fn delete_model(
+    state: tauri::State<'_, StorageState>,
+    asr: tauri::State<'_, AsrState>,
+    version: String,
+    force: Option<bool>
+) -> Result<(), String> {
+    let model_version = match version.as_str() {
+        "quantized" => ModelVersion::Quantized,
+        "unquantized" => ModelVersion::Unquantized,
+        _ => return Err("Invalid version".to_string()),
+    };
+
+    let config = state.load_config();
+
+    // The currently selected version is the one held by the recognizer
+    let is_loaded = config.model_version == model_version && asr.is_loaded();
+    if is_loaded {
+        if !force.unwrap_or(false) {
+            return Err("Model version is currently loaded".to_string());
+        }
+        asr.unload_model();
+    }
+
+    model_manager::delete_model_version(&config.model_dir, &model_version).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_model_storage_info(state: tauri::State<'_, StorageState>) -> Result<Vec<model_manager::ModelStorageInfo>, String> {
+    let config = state.load_config();
+    Ok(model_manager::get_model_storage_info(&config.model_dir))
+}
+
 #[tauri::command]
 async fn open_model_folder(state: tauri::State<'_, StorageState>) -> Result<(), String> {
     let config = state.load_config();
@@ -533,9 +1025,9 @@ fn switch_input_device<R: Runtime>(
     }
     
     // Save to config
-    let mut config = storage.load_config();
-    config.input_device = device_name;
-    storage.save_config(&config).map_err(|e| e.to_string())?;
+    storage
+        .update_config(|config| config.input_device = device_name)
+        .map_err(|e| e.to_string())?;
     
     Ok(())
 }
@@ -559,8 +1051,43 @@ fn stop_audio_test(audio: tauri::State<AudioState>) -> Result<(), String> {
 }
 
 #[tauri::command]
-async fn test_llm_connection(config: LlmConfig, proxy: ProxyConfig) -> Result<String, String> {
-    llm::test_connection(&config, &proxy).await.map_err(|e| e.to_string())
+async fn test_llm_connection(
+    state: tauri::State<'_, StorageState>,
+    config: LlmConfig,
+    proxy: ProxyConfig
+) -> Result<llm::ConnectionTestResult, String> {
+    // The webview only holds references to stored keys, or a newly typed key
+    let config = state.resolve_llm_config(&config);
+    llm::test_connection(&config, &proxy)
+        .await
+        .map_err(|e| secrets::redact(&e.to_string(), &[&config.api_key]))
+}
+
+#[tauri::command]
+async fn list_llm_models(
+    state: tauri::State<'_, StorageState>,
+    config: LlmConfig,
+    proxy: ProxyConfig
+) -> Result<Vec<String>, String> {
+    let config = state.resolve_llm_config(&config);
+    llm::list_models(&config, &proxy)
+        .await
+        .map_err(|e| secrets::redact(&e.to_string(), &[&config.api_key]))
+}
+
+#[derive(Serialize)]
+pub struct LlmProviderCapabilities {
+    requires_api_key: bool,
+    supports_json_schema: bool,
+}
+
+#[tauri::command]
+fn get_llm_provider_capabilities(provider: storage::LlmProvider) -> LlmProviderCapabilities {
+    let provider = llm_provider::provider_for(&provider);
+    LlmProviderCapabilities {
+        requires_api_key: provider.requires_api_key(),
+        supports_json_schema: provider.supports_json_schema(),
+    }
 }
 
 #[tauri::command]
@@ -608,25 +1135,41 @@ pub fn run() {
 
             // Initialize Storage (config in AppData\Roaming)
             let app_dir = app.path().app_data_dir().unwrap_or_else(|_| std::path::PathBuf::from("data"));
-            let storage_service = storage::StorageService::new(app_dir.clone());
-            let mut config = storage_service.load_config();
 
             // Use AppData\Local for models (less likely to be deleted on uninstall)
             let local_data_dir = app.path().app_local_data_dir().unwrap_or_else(|_| app_dir.clone());
 
-            // Initialize Services
-            let asr_service = asr::AsrService::new();
+            let storage_service = storage::StorageService::new(app_dir, local_data_dir);
+            let mut config = storage_service.init_config();
+
+            // Move API keys saved in plaintext by earlier versions into the secret store
+            if config.llm_configs().any(secrets::has_plaintext_keys) {
+                match storage_service.update_config(|_| {}) {
+                    Ok(saved) => config = saved,
+                    Err(e) => eprintln!("Failed to move API keys to the secret store: {}", e),
+                }
+            }
 
-            // Fix model path to be in AppData\Local if it's the default relative path
-            // This prevents "Rebuilding application" loops during download when running in dev mode
-            // and keeps models separate from app data that may be cleaned on uninstall
-            if config.model_dir == "./models/sense-voice" || config.model_dir.contains("AppData\\Roaming") {
-                let model_path = local_data_dir.join("models").join("sense-voice");
-                config.model_dir = model_path.to_string_lossy().to_string();
-                let _ = storage_service.save_config(&config);
-                println!("Updated model directory to: {}", config.model_dir);
+            // Enforce history retention limits
+            match storage_service.prune_history() {
+                Ok(0) => {}
+                Ok(removed) => println!("[HISTORY] Removed {} items past the retention limits", removed),
+                Err(e) => eprintln!("[HISTORY] Failed to apply retention limits: {}", e),
             }
 
+            // Tell the frontend when settings or history change, whoever changed them
+            let handle_for_changes = app_handle.clone();
+            storage_service.subscribe(move |change| {
+                let event = match change {
+                    storage::StorageChange::Config => "config_changed",
+                    storage::StorageChange::History => "history_changed",
+                };
+                handle_for_changes.emit(event, ()).ok();
+            });
+
+            // Initialize Services
+            let asr_service = asr::AsrService::new();
+
             // Load model for the selected version if it exists (in background)
             let asr_for_loading = asr_service.clone();
             let app_handle_for_loading = app_handle.clone();
@@ -637,6 +1180,7 @@ pub fn run() {
                     let model_path = model_manager::get_model_dir_for_version(&config_for_loading.model_dir, &config_for_loading.model_version);
                     match asr_for_loading.load_model(model_path, config_for_loading.language.clone()) {
                         Ok(_) => {
+                            model_manager::mark_model_used(&config_for_loading.model_dir, &config_for_loading.model_version);
                             // Emit event that model is loaded
                             app_handle_for_loading.emit("model_loaded", ()).ok();
                         },
@@ -645,6 +1189,9 @@ pub fn run() {
                 }
             });
 
+            // Periodic model update check (no-op while disabled in settings)
+            tauri::async_runtime::spawn(run_model_update_checks(app_handle.clone()));
+
             let mut audio_service = audio::AudioService::new();
 
             // Try to initialize with configured device, fallback to default if it fails
@@ -690,10 +1237,12 @@ pub fn run() {
             let processing_for_thread = processing_state.clone();
             std::thread::spawn(move || {
                 let mut is_recording = false;
+                // The trigger that started the current recording decides its LLM mode
+                let mut active_trigger = input_listener::Trigger::Hold;
 
                 for event in rx {
                     match event {
-                        input_listener::InputEvent::Start => {
+                        input_listener::InputEvent::Start(trigger) => {
                             if !is_recording && !processing_for_thread.load(std::sync::atomic::Ordering::SeqCst) {
                                 // Start Recording
                                 let audio = app_handle.state::<AudioState>();
@@ -706,6 +1255,7 @@ pub fn run() {
                                 };
                                 if started {
                                     is_recording = true;
+                                    active_trigger = trigger;
                                     app_handle.emit("recording_status", true).ok();
                                     // Enable mouse tracking for indicator window
                                     let listener = app_handle.state::<InputListenerState>();
@@ -715,7 +1265,7 @@ pub fn run() {
                                 }
                             }
                         },
-                        input_listener::InputEvent::Stop => {
+                        input_listener::InputEvent::Stop(_) => {
                             if is_recording && !processing_for_thread.load(std::sync::atomic::Ordering::SeqCst) {
                                 // Stop & Transcribe
                                 is_recording = false;
@@ -753,15 +1303,16 @@ pub fn run() {
                                     let asr = app_handle.state::<AsrState>();
                                     // Transcribe with actual sample rate
                                     match asr.transcribe(buffer, sample_rate) {
-                                        Ok(text) => {
+                                        Ok(transcription) => {
+                                            let text = &transcription.text;
                                             let seq_id = TRANSCRIPTION_SEQ.fetch_add(1, AtomicOrdering::Relaxed);
                                             println!(
                                                 "[STOP] #{} Transcribed {} chars, preview='{}'",
                                                 seq_id,
                                                 text.len(),
-                                                preview_text(&text, 80)
+                                                preview_text(text, 80)
                                             );
-                                            process_transcription(&app_handle, text, processing_for_thread.clone(), seq_id);
+                                            process_transcription(&app_handle, transcription, processing_for_thread.clone(), seq_id, active_trigger);
                                         },
                                         Err(e) => {
                                             eprintln!("[STOP] Transcription error: {}", e);
@@ -805,15 +1356,16 @@ pub fn run() {
 
                                     let asr = app_handle.state::<AsrState>();
                                     match asr.transcribe(buffer, sample_rate) {
-                                        Ok(text) => {
+                                        Ok(transcription) => {
+                                            let text = &transcription.text;
                                             let seq_id = TRANSCRIPTION_SEQ.fetch_add(1, AtomicOrdering::Relaxed);
                                             println!(
                                                 "[TOGGLE] #{} Transcribed {} chars, preview='{}'",
                                                 seq_id,
                                                 text.len(),
-                                                preview_text(&text, 80)
+                                                preview_text(text, 80)
                                             );
-                                            process_transcription(&app_handle, text, processing_for_thread.clone(), seq_id);
+                                            process_transcription(&app_handle, transcription, processing_for_thread.clone(), seq_id, active_trigger);
                                         },
                                         Err(e) => {
                                             eprintln!("[TOGGLE] Transcription error: {}", e);
@@ -832,6 +1384,7 @@ pub fn run() {
                                 };
                                 if started {
                                     is_recording = true;
+                                    active_trigger = input_listener::Trigger::Toggle;
                                     app_handle.emit("recording_status", true).ok();
                                     // Enable mouse tracking
                                     let listener = app_handle.state::<InputListenerState>();
@@ -841,6 +1394,19 @@ pub fn run() {
                                 }
                             }
                         },
+                        input_listener::InputEvent::NextProfile => {
+                            // Don't change devices or models under a running dictation
+                            if is_recording || processing_for_thread.load(std::sync::atomic::Ordering::SeqCst) {
+                                continue;
+                            }
+                            let next = app_handle.state::<StorageState>().load_config().next_profile().map(str::to_string);
+                            if let Some(name) = next {
+                                match activate_profile(&app_handle, &name) {
+                                    Ok(()) => println!("[PROFILE] Switched to {}", name),
+                                    Err(e) => eprintln!("[PROFILE] Failed to switch to {}: {}", name, e),
+                                }
+                            }
+                        },
                         input_listener::InputEvent::MouseMove { x, y } => {
                             // Move indicator window to follow mouse
                             move_indicator_window(&app_handle, x, y);
@@ -860,13 +1426,17 @@ pub fn run() {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
-            get_config, save_config, get_history, clear_history,
+            get_config, get_config_error, save_config, patch_config,
+            save_profile, delete_profile, switch_profile, export_config, import_config,
+            get_history, clear_history, delete_history_item, edit_history_item, set_history_item_pinned, set_history_item_tags,
+            get_llm_usage_stats, get_dictation_stats, export_history, import_history,
             check_model_status, download_model, open_model_folder,
             get_model_versions_status, get_model_detailed_status,
             download_model_for_version, switch_model_version, cancel_download, import_model,
+            delete_model, get_model_storage_info, check_model_update, download_model_update, dismiss_model_update,
             get_input_devices, get_current_input_device, switch_input_device,
             start_audio_test, stop_audio_test,
-            test_llm_connection, get_default_llm_prompt
+            test_llm_connection, list_llm_models, get_default_llm_prompt, get_llm_provider_capabilities
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
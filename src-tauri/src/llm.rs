@@ -1,39 +1,46 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
 use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
-use crate::storage::{LlmConfig, ProxyConfig, DEFAULT_LLM_PROMPT};
+use crate::storage::{HistoryItem, LlmConfig, LlmContextConfig, ProxyConfig, TokenUsage, TranslationConfig, TriggerLlmMode, DEFAULT_LLM_PROMPT};
 use crate::http_client::build_client;
-
-#[derive(Serialize)]
-struct ChatMessage {
-    role: String,
-    content: String,
-}
-
-#[derive(Serialize)]
-struct ChatRequest {
-    model: String,
-    messages: Vec<ChatMessage>,
-    temperature: f32,
-}
+use crate::script::Script;
+use crate::secrets;
+use crate::llm_provider::{provider_for, ChatMessage, ChatOptions, StreamEvent};
 
 #[derive(Deserialize)]
-struct ChatChoice {
-    message: ChatMessageResponse,
+struct CorrectionResult {
+    corrected: String,
 }
 
-#[derive(Deserialize)]
-struct ChatMessageResponse {
-    content: String,
+/// A corrected text with the model that produced it and what it cost
+#[derive(Debug, Clone)]
+pub struct Correction {
+    pub text: String,
+    pub model: String,
+    pub usage: Option<TokenUsage>,
 }
 
-#[derive(Deserialize)]
-struct ChatResponse {
-    choices: Vec<ChatChoice>,
+/// JSON schema of CorrectionResult, used for structured output
+fn correction_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "corrected": { "type": "string" }
+        },
+        "required": ["corrected"],
+        "additionalProperties": false
+    })
 }
 
-#[derive(Deserialize)]
-struct CorrectionResult {
-    corrected: String,
+/// Non-2xx answer from an LLM endpoint
+#[derive(Debug, thiserror::Error)]
+#[error("LLM API error ({status}): {body}")]
+struct ApiError {
+    status: reqwest::StatusCode,
+    body: String,
 }
 
 /// Extract JSON from LLM response, handling potential markdown code blocks
@@ -63,110 +70,833 @@ fn extract_json(text: &str) -> &str {
     text
 }
 
-/// Correct text using LLM
-pub async fn correct_text(text: &str, config: &LlmConfig, proxy: &ProxyConfig) -> Result<String> {
-    if !config.enabled || config.api_key.is_empty() {
-        return Ok(text.to_string());
-    }
-
+/// Build the correction prompt for the given text
+fn build_prompt(text: &str, config: &LlmConfig) -> String {
     let prompt = if config.custom_prompt.is_empty() {
         DEFAULT_LLM_PROMPT.to_string()
     } else {
         config.custom_prompt.clone()
     };
 
-    let prompt = prompt.replace("{text}", text);
-
-    let client = build_client(proxy, 30)?;
+    prompt.replace("{text}", text)
+}
 
-    let url = format!("{}/chat/completions", config.base_url.trim_end_matches('/'));
+/// Rough token count: CJK characters are about a token each, other text about four characters per token
+fn estimate_tokens(text: &str) -> usize {
+    let cjk = text.chars().filter(|&c| Script::of(c).is_cjk()).count();
+    let other = text.chars().count() - cjk;
+    cjk + other.div_ceil(4)
+}
 
-    let request = ChatRequest {
-        model: config.model.clone(),
-        messages: vec![ChatMessage {
-            role: "user".to_string(),
-            content: prompt,
-        }],
-        temperature: 0.3,
+/// System message with the glossary and recent dictation (newest first), cut to the token budget.
+/// Glossary terms take precedence; older dictation is dropped first.
+fn context_message(context: &LlmContextConfig, recent_dictation: &[String]) -> Option<ChatMessage> {
+    let mut budget = context.max_context_tokens;
+    let mut fits = |text: &str| {
+        let cost = estimate_tokens(text) + 1;
+        let fits = cost <= budget;
+        if fits {
+            budget -= cost;
+        }
+        fits
     };
 
-    let response = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", config.api_key))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-        .await?;
+    let glossary: Vec<&str> = context
+        .glossary
+        .iter()
+        .map(|term| term.trim())
+        .filter(|term| !term.is_empty())
+        .take_while(|term| fits(term))
+        .collect();
+    let history: Vec<&str> = recent_dictation
+        .iter()
+        .map(|item| item.trim())
+        .take_while(|item| fits(item))
+        .collect();
+    if glossary.is_empty() && history.is_empty() {
+        return None;
+    }
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(anyhow!("LLM API error ({}): {}", status, error_text));
+    let mut content = String::from("以下内容仅供参考，用于正确识别人名、术语和上下文指代；不要修改、翻译或输出这些内容。");
+    if !glossary.is_empty() {
+        content.push_str("\n\n术语表：");
+        content.push_str(&glossary.join("、"));
+    }
+    if !history.is_empty() {
+        content.push_str("\n\n用户之前的听写内容（按时间顺序）：");
+        for item in history.iter().rev() {
+            content.push_str("\n- ");
+            content.push_str(item);
+        }
     }
+    Some(ChatMessage::system(content))
+}
 
-    let chat_response: ChatResponse = response.json().await?;
+/// Messages for one correction: optional context, then the prompt
+fn build_messages(text: &str, config: &LlmConfig, recent_dictation: &[String]) -> Vec<ChatMessage> {
+    context_message(&config.context, recent_dictation)
+        .into_iter()
+        .chain(std::iter::once(ChatMessage::user(build_prompt(text, config))))
+        .collect()
+}
 
-    let content = chat_response
-        .choices
-        .first()
-        .map(|c| c.message.content.clone())
-        .ok_or_else(|| anyhow!("Empty response from LLM"))?;
+/// Recent dictation to send as context, newest first, within the configured count and time window
+pub fn recent_dictation(history: &[HistoryItem], context: &LlmContextConfig) -> Vec<String> {
+    if !context.include_history {
+        return Vec::new();
+    }
 
-    // Parse JSON response
-    let json_str = extract_json(&content);
+    let now = chrono::Local::now().naive_local();
+    let window = chrono::Duration::seconds(context.history_window_secs as i64);
+    history
+        .iter()
+        .take(context.history_items)
+        .take_while(|item| {
+            chrono::NaiveDateTime::parse_from_str(&item.timestamp, "%Y-%m-%d %H:%M:%S")
+                .is_ok_and(|time| now - time <= window)
+        })
+        // Translations are kept in the language that was dictated
+        .map(|item| item.original_text.clone().unwrap_or_else(|| item.text.clone()))
+        .collect()
+}
+
+/// Parse the corrected text out of the LLM answer, falling back to the original text
+fn parse_correction(text: &str, content: &str) -> String {
+    // Structured output is plain JSON; free text needs the fenced/brace extraction fallback
+    if let Ok(result) = serde_json::from_str::<CorrectionResult>(content.trim()) {
+        return result.corrected;
+    }
+    let json_str = extract_json(content);
 
     match serde_json::from_str::<CorrectionResult>(json_str) {
-        Ok(result) => Ok(result.corrected),
+        Ok(result) => result.corrected,
         Err(_) => {
             // If JSON parsing fails, try to use the content directly
             // This handles cases where LLM returns plain text
             eprintln!("LLM returned non-JSON response, using original text: {}", content);
-            Ok(text.to_string())
+            text.to_string()
         }
     }
 }
 
-/// Test LLM connection with a simple request
-pub async fn test_connection(config: &LlmConfig, proxy: &ProxyConfig) -> Result<String> {
-    if config.api_key.is_empty() {
-        return Err(anyhow!("API Key is empty"));
+/// Whether LLM correction is enabled and has the credentials its provider needs
+pub fn is_configured(config: &LlmConfig) -> bool {
+    config.enabled && (!config.api_key.is_empty() || !provider_for(&config.provider).requires_api_key())
+}
+
+/// Name of a language code SenseVoice reports, for prompts; other values are used as-is
+fn language_name(code: &str) -> &str {
+    match code {
+        "zh" => "中文",
+        "en" => "英文",
+        "ja" => "日文",
+        "ko" => "韩文",
+        "yue" => "粤语",
+        other => other,
     }
+}
 
-    let client = build_client(proxy, 10)?;
+/// Prompt that corrects recognition errors and translates the result
+fn translation_prompt(source_language: &str, target_language: &str) -> String {
+    let source = if source_language.is_empty() {
+        "原文".to_string()
+    } else {
+        format!("原文语言为{}", language_name(source_language))
+    };
+    format!(
+        r#"你是一个语音输入翻译助手。用户会提供语音识别的原始文本（{}），其中可能包含同音字、漏字和标点错误。
+
+请先在理解上修正这些错误，然后将其翻译为{}。
+
+重要规则：
+1. 忠实翻译原意，不要增加、删减或回答其中的内容
+2. 只输出译文，不要包含原文或解释
+3. 必须以 JSON 格式返回结果
+
+输入文本：{{text}}
+
+请以如下 JSON 格式返回（不要包含其他内容）：
+{{"corrected": "译文"}}"#,
+        source,
+        language_name(target_language.trim())
+    )
+}
+
+/// Whether speech detected as `language` is translated, rather than just corrected, in translation mode
+pub fn should_translate(config: &TranslationConfig, language: &str) -> bool {
+    !(config.correct_same_language && language.eq_ignore_ascii_case(config.target_language.trim()))
+}
+
+/// The LLM config a trigger's transcription runs with, or None when the trigger skips the LLM.
+/// An unknown profile id falls back to the main prompt.
+pub fn config_for_mode(config: &LlmConfig, mode: &TriggerLlmMode, language: &str) -> Option<LlmConfig> {
+    match mode {
+        TriggerLlmMode::Off => None,
+        TriggerLlmMode::Default => Some(config.clone()),
+        TriggerLlmMode::Translate if should_translate(&config.translation, language) => {
+            let mut resolved = config.clone();
+            resolved.custom_prompt = translation_prompt(language, &config.translation.target_language);
+            // A translation never stays close to the recognized text
+            resolved.guardrail.enabled = false;
+            Some(resolved)
+        }
+        TriggerLlmMode::Translate => Some(config.clone()),
+        TriggerLlmMode::Profile(id) => {
+            let mut resolved = config.clone();
+            match config.prompt_profiles.iter().find(|p| &p.id == id) {
+                Some(profile) => {
+                    resolved.custom_prompt = profile.prompt.clone();
+                    resolved.guardrail.enabled &= profile.apply_guardrail;
+                }
+                None => eprintln!("[LLM] Unknown prompt profile '{}', using the main prompt", id),
+            }
+            Some(resolved)
+        }
+    }
+}
+
+/// Base delay of the exponential backoff between retries
+const RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// Longest wait we accept before retrying the same endpoint; longer Retry-After values fail over instead
+const RETRY_MAX_DELAY_MS: u64 = 10_000;
 
-    let url = format!("{}/chat/completions", config.base_url.trim_end_matches('/'));
+/// Circuit breaker state of an endpoint
+#[derive(Default)]
+struct Circuit {
+    failures: u32, // Consecutive outages
+    open_until: Option<Instant>,
+}
+
+/// Circuit breakers of the endpoints that failed recently, keyed by endpoint
+static CIRCUITS: LazyLock<Mutex<HashMap<String, Circuit>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn endpoint_key(config: &LlmConfig) -> String {
+    format!("{}|{}", config.base_url.trim_end_matches('/'), config.model)
+}
+
+fn circuit_is_open(config: &LlmConfig) -> bool {
+    let mut circuits = CIRCUITS.lock().unwrap();
+    match circuits.get(&endpoint_key(config)).and_then(|c| c.open_until) {
+        Some(until) if Instant::now() < until => true,
+        Some(_) => {
+            // Cooldown elapsed: close the circuit and allow a new attempt
+            circuits.remove(&endpoint_key(config));
+            false
+        }
+        None => false,
+    }
+}
+
+/// Whether an error means the endpoint is down or overloaded (network errors, 429, 5xx),
+/// as opposed to a problem with this request (bad key, unknown model, unparseable answer)
+fn is_outage(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<ApiError>() {
+        Some(e) => e.status == reqwest::StatusCode::TOO_MANY_REQUESTS || e.status.is_server_error(),
+        None => error.downcast_ref::<reqwest::Error>().is_some_and(|e| !e.is_decode()),
+    }
+}
+
+/// Success closes the circuit; it opens after `circuit_breaker_threshold` consecutive outages
+fn record_endpoint_result(config: &LlmConfig, result: Result<(), &anyhow::Error>) {
+    let mut circuits = CIRCUITS.lock().unwrap();
+    match result {
+        Ok(()) => {
+            circuits.remove(&endpoint_key(config));
+        }
+        Err(e) if is_outage(e) => {
+            let circuit = circuits.entry(endpoint_key(config)).or_default();
+            circuit.failures += 1;
+            if circuit.failures >= config.circuit_breaker_threshold.max(1) {
+                let cooldown = Duration::from_secs(config.circuit_breaker_cooldown_secs);
+                circuit.open_until = Some(Instant::now() + cooldown);
+            }
+        }
+        Err(_) => {}
+    }
+}
+
+/// Endpoints to try in order: the primary config followed by its fallbacks,
+/// which inherit prompt and streaming settings from the primary
+fn endpoint_chain(config: &LlmConfig) -> Vec<LlmConfig> {
+    let mut chain = vec![config.clone()];
+    chain.extend(config.fallback_endpoints.iter().map(|endpoint| LlmConfig {
+        provider: endpoint.provider.clone(),
+        base_url: endpoint.base_url.clone(),
+        api_key: endpoint.api_key.clone(),
+        model: endpoint.model.clone(),
+        api_version: endpoint.api_version.clone(),
+        timeout_secs: endpoint.timeout_secs,
+        structured_output: endpoint.structured_output,
+        fallback_endpoints: Vec::new(),
+        ..config.clone()
+    }));
+    chain
+}
+
+/// Parse a Retry-After header given either as seconds or as an HTTP date
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().ok()
+}
+
+/// Exponential backoff with ±50% jitter so concurrent clients don't retry in lockstep
+fn backoff_delay(attempt: u32) -> Duration {
+    let base = RETRY_BASE_DELAY_MS.saturating_mul(1 << attempt.min(6));
+    // uuid v4 is already our source of randomness; no need for a separate RNG
+    let jitter = (uuid::Uuid::new_v4().as_u128() % 1000) as u64; // 0..1000 per mille
+    let delay = base / 2 + base * jitter / 1000;
+    Duration::from_millis(delay.min(RETRY_MAX_DELAY_MS))
+}
+
+/// Send a chat request to the configured provider, retrying transport errors, 429 and 5xx
+/// responses with jittered backoff (honoring Retry-After), and erroring on other non-2xx status
+async fn send_chat_request(
+    config: &LlmConfig,
+    proxy: &ProxyConfig,
+    messages: &[ChatMessage],
+    options: &ChatOptions<'_>
+) -> Result<reqwest::Response> {
+    let client = build_client(proxy, config.timeout_secs)?;
+    let provider = provider_for(&config.provider);
+    let mut attempt: u32 = 0;
+
+    loop {
+        let (error, delay) = match provider.request(&client, config, messages, options).send().await {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => {
+                let status = response.status();
+                let retryable = status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+                let delay = retry_after(&response).unwrap_or_else(|| backoff_delay(attempt));
+                let body = secrets::redact(&response.text().await.unwrap_or_default(), &[&config.api_key]);
+                let error = ApiError { status, body }.into();
+                if !retryable {
+                    return Err(error);
+                }
+                (error, delay)
+            }
+            Err(e) => (e.into(), backoff_delay(attempt)),
+        };
 
-    let request = ChatRequest {
+        if attempt >= config.max_retries || delay > Duration::from_millis(RETRY_MAX_DELAY_MS) {
+            return Err(error);
+        }
+        attempt += 1;
+        eprintln!("[LLM] {} failed ({}), retry {}/{} in {} ms", config.base_url, error, attempt, config.max_retries, delay.as_millis());
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Endpoints that rejected structured output, which are sent free-text requests from then on
+static SCHEMA_UNSUPPORTED: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Whether a client error response names the `response_format` / JSON schema parameter
+fn names_json_schema(error: &ApiError) -> bool {
+    let body = error.body.to_lowercase();
+    error.status.is_client_error()
+        && ["response_format", "json_schema", "unsupported parameter"].iter().any(|hint| body.contains(hint))
+}
+
+/// Whether an error response may mean the endpoint doesn't accept `response_format` / JSON schema:
+/// a client error that names the parameter, or a bare 422
+fn may_reject_json_schema(error: &ApiError) -> bool {
+    names_json_schema(error) || error.status == reqwest::StatusCode::UNPROCESSABLE_ENTITY
+}
+
+/// Send a correction request, constrained to the CorrectionResult schema when the provider
+/// and endpoint support it. Endpoints that reject the schema are retried once without it and
+/// remembered, leaving the free-text JSON extraction as fallback.
+async fn send_with_structured_fallback(
+    config: &LlmConfig,
+    proxy: &ProxyConfig,
+    messages: &[ChatMessage],
+    temperature: f32,
+    stream: bool
+) -> Result<reqwest::Response> {
+    let schema = correction_schema();
+    let structured = config.structured_output
+        && provider_for(&config.provider).supports_json_schema()
+        && !SCHEMA_UNSUPPORTED.lock().unwrap().contains(&endpoint_key(config));
+    let options = ChatOptions {
+        temperature,
+        stream,
+        json_schema: structured.then_some(&schema),
+    };
+
+    match send_chat_request(config, proxy, messages, &options).await {
+        Err(e) if structured && e.downcast_ref::<ApiError>().is_some_and(may_reject_json_schema) => {
+            eprintln!("[LLM] {} rejected structured output, retrying without schema: {}", config.base_url, e);
+            // Only remember endpoints that said so, a 422 without a hint may be about anything else
+            let remember = e.downcast_ref::<ApiError>().is_some_and(names_json_schema);
+            let options = ChatOptions { json_schema: None, ..options };
+            let res = send_chat_request(config, proxy, messages, &options).await;
+            if res.is_ok() && remember {
+                SCHEMA_UNSUPPORTED.lock().unwrap().insert(endpoint_key(config));
+            }
+            res
+        }
+        res => res,
+    }
+}
+
+/// Correct text with a single endpoint
+async fn correct_with_endpoint(text: &str, recent_dictation: &[String], config: &LlmConfig, proxy: &ProxyConfig) -> Result<Correction> {
+    let provider = provider_for(&config.provider);
+    let messages = build_messages(text, config, recent_dictation);
+    let response = send_with_structured_fallback(config, proxy, &messages, 0.3, false).await?;
+
+    let body: serde_json::Value = response.json().await?;
+    let content = provider
+        .parse_response(&body)
+        .ok_or_else(|| anyhow!("Empty response from LLM"))?;
+
+    // Parse JSON response
+    Ok(Correction {
+        text: parse_correction(text, &content),
         model: config.model.clone(),
-        messages: vec![ChatMessage {
-            role: "user".to_string(),
-            content: "Say 'OK' to confirm connection.".to_string(),
-        }],
-        temperature: 0.0,
+        usage: provider.parse_usage(&body),
+    })
+}
+
+/// Correct text using LLM, falling back through the configured endpoints.
+/// recent_dictation (newest first, see `recent_dictation`) is sent as context within the token budget.
+pub async fn correct_text(text: &str, recent_dictation: &[String], config: &LlmConfig, proxy: &ProxyConfig) -> Result<Correction> {
+    if !is_configured(config) {
+        return Ok(Correction { text: text.to_string(), model: String::new(), usage: None });
+    }
+
+    let mut last_error = None;
+    for endpoint in endpoint_chain(config) {
+        if circuit_is_open(&endpoint) {
+            println!("[LLM] skipping {} (circuit open)", endpoint.base_url);
+            continue;
+        }
+        match correct_with_endpoint(text, recent_dictation, &endpoint, proxy).await {
+            Ok(corrected) => {
+                record_endpoint_result(&endpoint, Ok(()));
+                return Ok(corrected);
+            }
+            Err(e) => {
+                eprintln!("[LLM] endpoint {} failed: {}", endpoint.base_url, e);
+                record_endpoint_result(&endpoint, Err(&e));
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| anyhow!("All LLM endpoints are cooling down after failures")))
+}
+
+/// Stream a correction from a single endpoint; `emitted` is set once any delta was passed on
+async fn correct_streaming_with_endpoint<F>(
+    text: &str,
+    recent_dictation: &[String],
+    config: &LlmConfig,
+    proxy: &ProxyConfig,
+    on_delta: &mut F,
+    emitted: &mut bool
+) -> Result<Correction>
+where F: FnMut(&str) {
+    let provider = provider_for(&config.provider);
+    let messages = build_messages(text, config, recent_dictation);
+    let response = send_with_structured_fallback(config, proxy, &messages, 0.3, true).await?;
+
+    let mut stream = response.bytes_stream();
+    // Raw bytes of the current incomplete line (a chunk may split a UTF-8 sequence)
+    let mut pending: Vec<u8> = Vec::new();
+    let mut content = String::new();
+    let mut decoder = CorrectedFieldDecoder::default();
+    let mut usage: Option<TokenUsage> = None;
+
+    'stream: while let Some(chunk) = stream.next().await {
+        pending.extend_from_slice(&chunk?);
+
+        while let Some(newline) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let (event, reported) = provider.parse_stream_line(line.trim());
+            if let Some(reported) = reported {
+                usage = Some(usage.unwrap_or_default().merge(reported));
+            }
+            match event {
+                StreamEvent::Delta(delta) => {
+                    content.push_str(&delta);
+                    let decoded = decoder.push(&delta);
+                    if !decoded.is_empty() {
+                        *emitted = true;
+                        on_delta(&decoded);
+                    }
+                }
+                StreamEvent::Done => break 'stream,
+                StreamEvent::Skip => {} // Comments, event names and blank separators
+            }
+        }
+    }
+
+    if content.is_empty() {
+        return Err(anyhow!("Empty response from LLM"));
+    }
+
+    Ok(Correction {
+        text: parse_correction(text, &content),
+        model: config.model.clone(),
+        usage,
+    })
+}
+
+/// Correct text using LLM with streaming (`stream: true`).
+/// on_delta is called with each newly decoded piece of the "corrected" field as it arrives,
+/// so the caller can start typing before the response is complete. The returned string is
+/// the final parsed correction, which callers should reconcile with what was already streamed.
+/// Fails over to the next endpoint only while nothing has been streamed yet.
+pub async fn correct_text_streaming<F>(
+    text: &str,
+    recent_dictation: &[String],
+    config: &LlmConfig,
+    proxy: &ProxyConfig,
+    mut on_delta: F
+) -> Result<Correction>
+where F: FnMut(&str) {
+    if !is_configured(config) {
+        return Ok(Correction { text: text.to_string(), model: String::new(), usage: None });
+    }
+
+    let mut last_error = None;
+    for endpoint in endpoint_chain(config) {
+        if circuit_is_open(&endpoint) {
+            println!("[LLM] skipping {} (circuit open)", endpoint.base_url);
+            continue;
+        }
+        let mut emitted = false;
+        match correct_streaming_with_endpoint(text, recent_dictation, &endpoint, proxy, &mut on_delta, &mut emitted).await {
+            Ok(corrected) => {
+                record_endpoint_result(&endpoint, Ok(()));
+                return Ok(corrected);
+            }
+            Err(e) => {
+                eprintln!("[LLM] endpoint {} failed: {}", endpoint.base_url, e);
+                record_endpoint_result(&endpoint, Err(&e));
+                if emitted {
+                    return Err(e);
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| anyhow!("All LLM endpoints are cooling down after failures")))
+}
+
+#[derive(Default, PartialEq)]
+enum DecoderState {
+    #[default]
+    SeekingKey,
+    SeekingValue,
+    InValue,
+    Done,
+}
+
+/// Incrementally decodes the string value of the "corrected" field from a partially
+/// received JSON answer, handling escapes split across chunks
+#[derive(Default)]
+struct CorrectedFieldDecoder {
+    buffer: String,
+    pos: usize,
+    state: DecoderState,
+}
+
+impl CorrectedFieldDecoder {
+    const KEY: &'static str = "\"corrected\"";
+
+    /// Append a chunk of the answer and return the newly decoded part of the value
+    fn push(&mut self, chunk: &str) -> String {
+        self.buffer.push_str(chunk);
+        let mut out = String::new();
+
+        if self.state == DecoderState::SeekingKey {
+            match self.buffer.find(Self::KEY) {
+                Some(idx) => {
+                    self.pos = idx + Self::KEY.len();
+                    self.state = DecoderState::SeekingValue;
+                }
+                None => return out,
+            }
+        }
+
+        if self.state == DecoderState::SeekingValue {
+            for (i, ch) in self.buffer[self.pos..].char_indices() {
+                match ch {
+                    ':' | ' ' | '\t' | '\r' | '\n' => continue,
+                    '"' => {
+                        self.pos += i + 1;
+                        self.state = DecoderState::InValue;
+                    }
+                    // Not a string value; leave it to the final parse
+                    _ => self.state = DecoderState::Done,
+                }
+                break;
+            }
+        }
+
+        if self.state == DecoderState::InValue {
+            let rest = &self.buffer[self.pos..];
+            let mut consumed = 0;
+            let mut chars = rest.char_indices();
+            while let Some((i, ch)) = chars.next() {
+                match ch {
+                    '"' => {
+                        self.state = DecoderState::Done;
+                        consumed = i + 1;
+                        break;
+                    }
+                    '\\' => match decode_escape(&rest[i..]) {
+                        Some((decoded, len)) => {
+                            out.push(decoded);
+                            consumed = i + len;
+                            // Skip the remaining bytes of the escape sequence
+                            while chars.offset() < consumed {
+                                chars.next();
+                            }
+                        }
+                        None => break, // Incomplete escape, wait for more data
+                    },
+                    _ => {
+                        out.push(ch);
+                        consumed = i + ch.len_utf8();
+                    }
+                }
+            }
+            self.pos += consumed;
+        }
+
+        out
+    }
+}
+
+/// Decode a JSON escape sequence at the start of s (which begins with a backslash).
+/// Returns the decoded char and the escape length in bytes, or None if s is incomplete.
+fn decode_escape(s: &str) -> Option<(char, usize)> {
+    let bytes = s.as_bytes();
+    let kind = *bytes.get(1)?;
+    let simple = match kind {
+        b'n' => Some('\n'),
+        b't' => Some('\t'),
+        b'r' => Some('\r'),
+        b'b' => Some('\u{8}'),
+        b'f' => Some('\u{c}'),
+        b'"' => Some('"'),
+        b'\\' => Some('\\'),
+        b'/' => Some('/'),
+        b'u' => None,
+        _ => return Some(('\u{fffd}', 2)),
     };
+    if let Some(ch) = simple {
+        return Some((ch, 2));
+    }
 
-    let response = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", config.api_key))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
+    let code = u32::from_str_radix(s.get(2..6)?, 16).unwrap_or(0xfffd);
+    if (0xd800..0xdc00).contains(&code) {
+        // High surrogate: the low surrogate must follow as another \uXXXX escape
+        if s.get(6..8)? != "\\u" {
+            return Some(('\u{fffd}', 6));
+        }
+        let low = u32::from_str_radix(s.get(8..12)?, 16).unwrap_or(0);
+        if !(0xdc00..0xe000).contains(&low) {
+            // Not a low surrogate: leave the next escape to be decoded on its own
+            return Some(('\u{fffd}', 6));
+        }
+        let combined = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+        return Some((char::from_u32(combined).unwrap_or('\u{fffd}'), 12));
+    }
+    Some((char::from_u32(code).unwrap_or('\u{fffd}'), 6))
+}
+
+/// Sample dictation with a recognition error, used to check the prompt's JSON output
+const JSON_TEST_TEXT: &str = "我门明天早上九点在会议室开会";
+
+/// Outcome of a connection test
+#[derive(Serialize, Debug)]
+pub struct ConnectionTestResult {
+    pub latency_ms: u64,
+    pub resolved_model: String, // As reported by the endpoint, else the configured model
+    pub response: String,
+    pub json_output_ok: bool, // Whether the current prompt yields a parseable {"corrected": ...}
+    pub json_output_detail: String, // The sample correction, or why it failed
+}
+
+/// Test LLM connection with a simple request, then check that the current prompt
+/// produces a JSON answer the correction pipeline can parse
+pub async fn test_connection(config: &LlmConfig, proxy: &ProxyConfig) -> Result<ConnectionTestResult> {
+    let provider = provider_for(&config.provider);
+    if config.api_key.is_empty() && provider.requires_api_key() {
+        return Err(anyhow!("API Key is empty"));
+    }
+
+    // Fail fast: a connection test should neither wait long nor retry
+    let ping_config = &LlmConfig { timeout_secs: 10, max_retries: 0, ..config.clone() };
+    let messages = [ChatMessage::user("Say 'OK' to confirm connection.")];
+    let options = ChatOptions { temperature: 0.0, stream: false, json_schema: None };
+    let started = Instant::now();
+    let response = send_chat_request(ping_config, proxy, &messages, &options)
         .await
         .map_err(|e| anyhow!("Connection failed: {}", e))?;
 
+    let body: serde_json::Value = response.json().await?;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let content = provider
+        .parse_response(&body)
+        .ok_or_else(|| anyhow!("Empty response"))?;
+
+    let (json_output_ok, json_output_detail) = check_json_output(&LlmConfig { max_retries: 0, ..config.clone() }, proxy).await;
+
+    Ok(ConnectionTestResult {
+        latency_ms,
+        resolved_model: provider.parse_model(&body).unwrap_or_else(|| config.model.clone()),
+        response: content.chars().take(100).collect(),
+        json_output_ok,
+        json_output_detail,
+    })
+}
+
+/// Run the sample text through the correction request as the pipeline sends it
+async fn check_json_output(config: &LlmConfig, proxy: &ProxyConfig) -> (bool, String) {
+    let messages = build_messages(JSON_TEST_TEXT, config, &[]);
+    let response = match send_with_structured_fallback(config, proxy, &messages, 0.3, false).await {
+        Ok(response) => response,
+        Err(e) => return (false, e.to_string()),
+    };
+    let content = match response.json::<serde_json::Value>().await {
+        Ok(body) => provider_for(&config.provider).parse_response(&body).unwrap_or_default(),
+        Err(e) => return (false, e.to_string()),
+    };
+
+    match serde_json::from_str::<CorrectionResult>(extract_json(&content)) {
+        Ok(result) => (true, result.corrected),
+        Err(e) => (false, format!("{}: {}", e, content.chars().take(100).collect::<String>())),
+    }
+}
+
+/// List the models the endpoint offers, sorted
+pub async fn list_models(config: &LlmConfig, proxy: &ProxyConfig) -> Result<Vec<String>> {
+    let provider = provider_for(&config.provider);
+    if config.api_key.is_empty() && provider.requires_api_key() {
+        return Err(anyhow!("API Key is empty"));
+    }
+
+    let client = build_client(proxy, 10)?;
+    let request = provider
+        .models_request(&client, config)
+        .ok_or_else(|| anyhow!("This provider can't list models; enter the model or deployment name"))?;
+    let response = request.send().await?;
     if !response.status().is_success() {
         let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(anyhow!("API error ({}): {}", status, error_text));
+        let body = secrets::redact(&response.text().await.unwrap_or_default(), &[&config.api_key]);
+        return Err(ApiError { status, body }.into());
     }
 
-    let chat_response: ChatResponse = response.json().await?;
+    let body: serde_json::Value = response.json().await?;
+    let mut models = provider.parse_models(&body);
+    models.sort();
+    models.dedup();
+    Ok(models)
+}
 
-    let content = chat_response
-        .choices
-        .first()
-        .map(|c| c.message.content.clone())
-        .ok_or_else(|| anyhow!("Empty response"))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed the chunks to a fresh decoder and collect everything it emits
+    fn decode_chunks(chunks: &[&str]) -> String {
+        let mut decoder = CorrectedFieldDecoder::default();
+        chunks.iter().map(|chunk| decoder.push(chunk)).collect()
+    }
+
+    #[test]
+    fn decodes_value_in_one_chunk() {
+        assert_eq!(decode_chunks(&[r#"{"corrected": "我们明天开会"}"#]), "我们明天开会");
+    }
+
+    #[test]
+    fn decodes_value_split_anywhere() {
+        let answer = r#"{"corrected" : "Hello, \"world\"\n"}"#;
+        for split in (1..answer.len()).filter(|&i| answer.is_char_boundary(i)) {
+            let (a, b) = answer.split_at(split);
+            assert_eq!(decode_chunks(&[a, b]), "Hello, \"world\"\n", "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn waits_for_split_simple_escape() {
+        let mut decoder = CorrectedFieldDecoder::default();
+        assert_eq!(decoder.push(r#"{"corrected": "a\"#), "a");
+        assert_eq!(decoder.push(r#"tb"}"#), "\tb");
+    }
+
+    #[test]
+    fn waits_for_split_unicode_escape() {
+        assert_eq!(decode_chunks(&[r#"{"corrected": "\u4f"#, r#"60好"}"#]), "你好");
+    }
+
+    #[test]
+    fn joins_surrogate_pair_split_across_chunks() {
+        assert_eq!(decode_chunks(&[r#"{"corrected": "\ud83d"#, r#"\ude00"}"#]), "😀");
+        assert_eq!(decode_chunks(&[r#"{"corrected": "\ud83d\u"#, r#"de00!"}"#]), "😀!");
+        assert_eq!(decode_chunks(&[r#"{"corrected": "\ud83d\ude"#, r#"00"}"#]), "😀");
+    }
+
+    #[test]
+    fn ignores_fields_after_the_value() {
+        assert_eq!(decode_chunks(&[r#"{"corrected": "ok", "note": "x"#, r#"yz"}"#]), "ok");
+    }
 
-    Ok(format!("Connection successful! Model response: {}", content.chars().take(100).collect::<String>()))
+    #[test]
+    fn skips_non_string_value() {
+        assert_eq!(decode_chunks(&[r#"{"corrected": null}"#]), "");
+    }
+
+    #[test]
+    fn decode_escape_needs_the_whole_sequence() {
+        assert_eq!(decode_escape(r"\"), None);
+        assert_eq!(decode_escape(r"\u12"), None);
+        assert_eq!(decode_escape(r"\ud83d"), None);
+        assert_eq!(decode_escape(r"\ud83d\ude"), None);
+    }
+
+    #[test]
+    fn decode_escape_lengths() {
+        assert_eq!(decode_escape(r"\n"), Some(('\n', 2)));
+        assert_eq!(decode_escape(r"\/"), Some(('/', 2)));
+        assert_eq!(decode_escape(r"\u00e9"), Some(('é', 6)));
+        assert_eq!(decode_escape(r"\ud83d\ude00"), Some(('😀', 12)));
+    }
+
+    #[test]
+    fn decode_escape_replaces_invalid_sequences() {
+        assert_eq!(decode_escape(r"\x"), Some(('\u{fffd}', 2)));
+        assert_eq!(decode_escape(r"\uzzzz"), Some(('\u{fffd}', 6)));
+        // A high surrogate without its low half doesn't swallow what follows
+        assert_eq!(decode_escape(r"\ud83dxxxxxx"), Some(('\u{fffd}', 6)));
+        assert_eq!(decode_chunks(&[r#"{"corrected": "\ud83dA"}"#]), "\u{fffd}A");
+        assert_eq!(decode_chunks(&[r#"{"corrected": "\ud83d\u0041"}"#]), "\u{fffd}A");
+    }
+
+    fn api_error(status: u16, body: &str) -> ApiError {
+        ApiError { status: reqwest::StatusCode::from_u16(status).unwrap(), body: body.to_string() }
+    }
+
+    #[test]
+    fn schema_rejection_needs_a_hint_to_be_remembered() {
+        let named = api_error(400, r#"{"error": "Unsupported parameter: 'response_format'"}"#);
+        assert!(may_reject_json_schema(&named) && names_json_schema(&named));
+
+        let bare_422 = api_error(422, "Unprocessable Entity");
+        assert!(may_reject_json_schema(&bare_422) && !names_json_schema(&bare_422));
+
+        assert!(!may_reject_json_schema(&api_error(400, "context length exceeded")));
+        assert!(!may_reject_json_schema(&api_error(500, "response_format")));
+    }
 }
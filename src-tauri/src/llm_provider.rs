@@ -0,0 +1,529 @@
+use reqwest::{Client, RequestBuilder};
+use serde::Serialize;
+use serde_json::{json, Value};
+use crate::storage::{LlmConfig, LlmProvider, TokenUsage};
+
+/// Anthropic API version sent with every Messages API request
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Default Azure OpenAI API version when none is configured; the first GA version supporting json_schema output
+const AZURE_DEFAULT_API_VERSION: &str = "2024-10-21";
+
+/// Anthropic requires an explicit output limit
+const ANTHROPIC_MAX_TOKENS: u32 = 4096;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ChatMessage {
+    pub role: String, // "system", "user" or "assistant"
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self { role: "system".to_string(), content: content.into() }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self { role: "user".to_string(), content: content.into() }
+    }
+}
+
+/// Per-request generation options
+pub struct ChatOptions<'a> {
+    pub temperature: f32,
+    pub stream: bool,
+    /// JSON schema the answer must follow; only honored by providers that support structured output
+    pub json_schema: Option<&'a Value>,
+}
+
+/// Name of the schema / tool used for structured output
+const STRUCTURED_OUTPUT_NAME: &str = "correction_result";
+
+/// Result of parsing one chunk of a streaming response body
+pub enum StreamEvent {
+    Delta(String),
+    Done,
+    Skip,
+}
+
+/// A chat API dialect. Each provider knows how to build its request and read its answers;
+/// transport (client, proxy, timeouts) is shared through `build_client`.
+pub trait ChatProvider: Send + Sync {
+    /// Build the HTTP request for a chat completion
+    fn request(&self, client: &Client, config: &LlmConfig, messages: &[ChatMessage], options: &ChatOptions) -> RequestBuilder;
+
+    /// Extract the answer text from a non-streaming response body
+    fn parse_response(&self, body: &Value) -> Option<String>;
+
+    /// The JSON payload of one line of a streaming response body, if it carries one
+    fn stream_payload<'a>(&self, line: &'a str) -> Option<&'a str> {
+        sse_data(line)
+    }
+
+    /// Read the event of one parsed chunk of a streaming response body
+    fn parse_stream_chunk(&self, chunk: &Value) -> StreamEvent;
+
+    /// Extract token usage from a response body or a streamed event; fields not reported are 0
+    fn parse_usage(&self, body: &Value) -> Option<TokenUsage>;
+
+    /// Parse one line of a streaming response body, once for both its event and its token usage
+    fn parse_stream_line(&self, line: &str) -> (StreamEvent, Option<TokenUsage>) {
+        let Some(payload) = self.stream_payload(line) else {
+            return (StreamEvent::Skip, None);
+        };
+        // OpenAI-style end marker, the only payload that isn't JSON
+        if payload == "[DONE]" {
+            return (StreamEvent::Done, None);
+        }
+        match serde_json::from_str::<Value>(payload) {
+            Ok(chunk) => (self.parse_stream_chunk(&chunk), self.parse_usage(&chunk)),
+            Err(_) => (StreamEvent::Skip, None),
+        }
+    }
+
+    /// Build the request listing the models the endpoint offers, if the API has one
+    fn models_request(&self, _client: &Client, _config: &LlmConfig) -> Option<RequestBuilder> {
+        None
+    }
+
+    /// Extract model ids from a model listing response
+    fn parse_models(&self, body: &Value) -> Vec<String> {
+        id_list(body, "/data", "id")
+    }
+
+    /// The model that actually answered, as reported in a response body
+    fn parse_model(&self, body: &Value) -> Option<String> {
+        body.get("model")?.as_str().map(|s| s.to_string())
+    }
+
+    /// Whether requests need an API key (local servers usually don't)
+    fn requires_api_key(&self) -> bool {
+        true
+    }
+
+    /// Whether the provider can constrain its answer to a JSON schema
+    /// (response_format, forced tool call or schema-constrained decoding)
+    fn supports_json_schema(&self) -> bool {
+        false
+    }
+}
+
+/// Get the implementation for a configured provider
+pub fn provider_for(provider: &LlmProvider) -> &'static dyn ChatProvider {
+    match provider {
+        LlmProvider::OpenAi => &OpenAiProvider,
+        LlmProvider::Anthropic => &AnthropicProvider,
+        LlmProvider::AzureOpenAi => &AzureOpenAiProvider,
+        LlmProvider::Ollama => &OllamaProvider,
+        LlmProvider::Gemini => &GeminiProvider,
+    }
+}
+
+/// Strip the `data:` prefix of an SSE line; other SSE lines carry nothing we need
+fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(|d| d.trim())
+}
+
+fn base_url(config: &LlmConfig) -> &str {
+    config.base_url.trim_end_matches('/')
+}
+
+/// Collect a string field of every object in the array at `pointer`
+fn id_list(body: &Value, pointer: &str, field: &str) -> Vec<String> {
+    body.pointer(pointer)
+        .and_then(|list| list.as_array())
+        .map(|list| list.iter().filter_map(|m| m.get(field)?.as_str().map(|s| s.to_string())).collect())
+        .unwrap_or_default()
+}
+
+/// Join all system messages into a single instruction, for APIs that take it separately
+fn system_instruction(messages: &[ChatMessage]) -> Option<String> {
+    let system: Vec<&str> = messages
+        .iter()
+        .filter(|m| m.role == "system")
+        .map(|m| m.content.as_str())
+        .collect();
+    (!system.is_empty()).then(|| system.join("\n\n"))
+}
+
+/// OpenAI `/chat/completions` and compatible endpoints (DeepSeek, vLLM, LM Studio, ...)
+pub struct OpenAiProvider;
+
+fn openai_body(config: &LlmConfig, messages: &[ChatMessage], options: &ChatOptions) -> Value {
+    let mut body = json!({
+        "model": config.model,
+        "messages": messages,
+        "temperature": options.temperature,
+    });
+    if options.stream {
+        body["stream"] = json!(true);
+    }
+    if let Some(schema) = options.json_schema {
+        body["response_format"] = json!({
+            "type": "json_schema",
+            "json_schema": { "name": STRUCTURED_OUTPUT_NAME, "strict": true, "schema": schema },
+        });
+    }
+    body
+}
+
+fn openai_parse_response(body: &Value) -> Option<String> {
+    body.pointer("/choices/0/message/content")?.as_str().map(|s| s.to_string())
+}
+
+/// Read a usage object's token counts under the given field names
+fn usage_fields(usage: &Value, prompt_field: &str, completion_field: &str) -> Option<TokenUsage> {
+    let prompt = usage.get(prompt_field).and_then(|v| v.as_u64());
+    let completion = usage.get(completion_field).and_then(|v| v.as_u64());
+    (prompt.is_some() || completion.is_some()).then(|| TokenUsage {
+        prompt_tokens: prompt.unwrap_or(0),
+        completion_tokens: completion.unwrap_or(0),
+    })
+}
+
+fn openai_parse_usage(body: &Value) -> Option<TokenUsage> {
+    usage_fields(body.get("usage")?, "prompt_tokens", "completion_tokens")
+}
+
+fn openai_parse_stream_chunk(chunk: &Value) -> StreamEvent {
+    chunk
+        .pointer("/choices/0/delta/content")
+        .and_then(|c| c.as_str())
+        .map_or(StreamEvent::Skip, |c| StreamEvent::Delta(c.to_string()))
+}
+
+impl ChatProvider for OpenAiProvider {
+    fn request(&self, client: &Client, config: &LlmConfig, messages: &[ChatMessage], options: &ChatOptions) -> RequestBuilder {
+        let mut body = openai_body(config, messages, options);
+        if options.stream {
+            // Streams only report usage when asked to, in a final chunk without choices
+            body["stream_options"] = json!({ "include_usage": true });
+        }
+
+        client
+            .post(format!("{}/chat/completions", base_url(config)))
+            .header("Authorization", format!("Bearer {}", config.api_key))
+            .header("Content-Type", "application/json")
+            .json(&body)
+    }
+
+    fn parse_response(&self, body: &Value) -> Option<String> {
+        openai_parse_response(body)
+    }
+
+    fn parse_stream_chunk(&self, chunk: &Value) -> StreamEvent {
+        openai_parse_stream_chunk(chunk)
+    }
+
+    fn parse_usage(&self, body: &Value) -> Option<TokenUsage> {
+        openai_parse_usage(body)
+    }
+
+    fn models_request(&self, client: &Client, config: &LlmConfig) -> Option<RequestBuilder> {
+        Some(
+            client
+                .get(format!("{}/models", base_url(config)))
+                .header("Authorization", format!("Bearer {}", config.api_key)),
+        )
+    }
+
+    fn supports_json_schema(&self) -> bool {
+        true
+    }
+}
+
+/// Azure OpenAI: deployment URL plus `api-key` header, OpenAI-shaped bodies.
+/// base_url is either the full deployment URL (".../openai/deployments/<name>")
+/// or the resource endpoint, in which case `model` is used as the deployment name.
+/// Deployments can't be listed with an API key, so there is no model listing.
+pub struct AzureOpenAiProvider;
+
+impl ChatProvider for AzureOpenAiProvider {
+    fn request(&self, client: &Client, config: &LlmConfig, messages: &[ChatMessage], options: &ChatOptions) -> RequestBuilder {
+        let deployment_url = if base_url(config).contains("/openai/deployments/") {
+            base_url(config).to_string()
+        } else {
+            format!("{}/openai/deployments/{}", base_url(config), config.model)
+        };
+        let api_version = if config.api_version.is_empty() {
+            AZURE_DEFAULT_API_VERSION
+        } else {
+            config.api_version.as_str()
+        };
+
+        client
+            .post(format!("{}/chat/completions", deployment_url))
+            .query(&[("api-version", api_version)])
+            .header("api-key", &config.api_key)
+            .header("Content-Type", "application/json")
+            .json(&openai_body(config, messages, options))
+    }
+
+    fn parse_response(&self, body: &Value) -> Option<String> {
+        openai_parse_response(body)
+    }
+
+    fn parse_stream_chunk(&self, chunk: &Value) -> StreamEvent {
+        openai_parse_stream_chunk(chunk)
+    }
+
+    // Older Azure API versions reject `stream_options`, so streamed requests go unmetered
+    fn parse_usage(&self, body: &Value) -> Option<TokenUsage> {
+        openai_parse_usage(body)
+    }
+
+    fn supports_json_schema(&self) -> bool {
+        true
+    }
+}
+
+/// Anthropic Messages API (`/v1/messages`)
+pub struct AnthropicProvider;
+
+impl ChatProvider for AnthropicProvider {
+    fn request(&self, client: &Client, config: &LlmConfig, messages: &[ChatMessage], options: &ChatOptions) -> RequestBuilder {
+        let conversation: Vec<&ChatMessage> = messages.iter().filter(|m| m.role != "system").collect();
+        let mut body = json!({
+            "model": config.model,
+            "max_tokens": ANTHROPIC_MAX_TOKENS,
+            "messages": conversation,
+            "temperature": options.temperature,
+        });
+        if let Some(system) = system_instruction(messages) {
+            body["system"] = json!(system);
+        }
+        if options.stream {
+            body["stream"] = json!(true);
+        }
+        // Structured output through a forced tool call whose input is the result object
+        if let Some(schema) = options.json_schema {
+            body["tools"] = json!([{
+                "name": STRUCTURED_OUTPUT_NAME,
+                "description": "Submit the result",
+                "input_schema": schema,
+            }]);
+            body["tool_choice"] = json!({ "type": "tool", "name": STRUCTURED_OUTPUT_NAME });
+        }
+
+        client
+            .post(format!("{}/messages", base_url(config)))
+            .header("x-api-key", &config.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header("Content-Type", "application/json")
+            .json(&body)
+    }
+
+    fn parse_response(&self, body: &Value) -> Option<String> {
+        let blocks = body.get("content")?.as_array()?;
+        // A forced tool call carries the structured result as its input
+        if let Some(tool_use) = blocks.iter().find(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_use")) {
+            return tool_use.get("input").map(|input| input.to_string());
+        }
+        Some(
+            blocks
+                .iter()
+                .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+                .filter_map(|b| b.get("text")?.as_str())
+                .collect(),
+        )
+    }
+
+    fn parse_stream_chunk(&self, event: &Value) -> StreamEvent {
+        match event.get("type").and_then(|t| t.as_str()) {
+            // Text deltas, or partial JSON of a forced tool call's input
+            Some("content_block_delta") => event
+                .pointer("/delta/text")
+                .or_else(|| event.pointer("/delta/partial_json"))
+                .and_then(|t| t.as_str())
+                .map_or(StreamEvent::Skip, |t| StreamEvent::Delta(t.to_string())),
+            Some("message_stop") => StreamEvent::Done,
+            _ => StreamEvent::Skip,
+        }
+    }
+
+    // Streams report input tokens in message_start and output tokens in message_delta
+    fn parse_usage(&self, body: &Value) -> Option<TokenUsage> {
+        let usage = body.get("usage").or_else(|| body.pointer("/message/usage"))?;
+        usage_fields(usage, "input_tokens", "output_tokens")
+    }
+
+    fn models_request(&self, client: &Client, config: &LlmConfig) -> Option<RequestBuilder> {
+        Some(
+            client
+                .get(format!("{}/models", base_url(config)))
+                .query(&[("limit", "1000")])
+                .header("x-api-key", &config.api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION),
+        )
+    }
+
+    fn supports_json_schema(&self) -> bool {
+        true
+    }
+}
+
+/// Ollama native API (`/api/chat`), streaming newline-delimited JSON
+pub struct OllamaProvider;
+
+impl ChatProvider for OllamaProvider {
+    fn request(&self, client: &Client, config: &LlmConfig, messages: &[ChatMessage], options: &ChatOptions) -> RequestBuilder {
+        // Ollama streams by default, so stream must always be sent explicitly
+        let mut body = json!({
+            "model": config.model,
+            "messages": messages,
+            "stream": options.stream,
+            "options": { "temperature": options.temperature },
+        });
+        if let Some(schema) = options.json_schema {
+            body["format"] = schema.clone();
+        }
+
+        let mut request = client
+            .post(format!("{}/api/chat", base_url(config)))
+            .header("Content-Type", "application/json")
+            .json(&body);
+        // Plain Ollama has no auth, but reverse proxies in front of it often do
+        if !config.api_key.is_empty() {
+            request = request.header("Authorization", format!("Bearer {}", config.api_key));
+        }
+        request
+    }
+
+    fn parse_response(&self, body: &Value) -> Option<String> {
+        body.pointer("/message/content")?.as_str().map(|s| s.to_string())
+    }
+
+    // Newline-delimited JSON rather than SSE
+    fn stream_payload<'a>(&self, line: &'a str) -> Option<&'a str> {
+        Some(line).filter(|line| !line.is_empty())
+    }
+
+    fn parse_stream_chunk(&self, chunk: &Value) -> StreamEvent {
+        if chunk.get("done").and_then(|d| d.as_bool()) == Some(true) {
+            return StreamEvent::Done;
+        }
+        chunk
+            .pointer("/message/content")
+            .and_then(|c| c.as_str())
+            .map_or(StreamEvent::Skip, |c| StreamEvent::Delta(c.to_string()))
+    }
+
+    // Reported on the final (`done`) object
+    fn parse_usage(&self, body: &Value) -> Option<TokenUsage> {
+        usage_fields(body, "prompt_eval_count", "eval_count")
+    }
+
+    fn models_request(&self, client: &Client, config: &LlmConfig) -> Option<RequestBuilder> {
+        Some(client.get(format!("{}/api/tags", base_url(config))))
+    }
+
+    fn parse_models(&self, body: &Value) -> Vec<String> {
+        id_list(body, "/models", "name")
+    }
+
+    fn requires_api_key(&self) -> bool {
+        false
+    }
+
+    fn supports_json_schema(&self) -> bool {
+        true
+    }
+}
+
+/// Google Gemini API (`models/<model>:generateContent`)
+pub struct GeminiProvider;
+
+fn gemini_text(body: &Value) -> Option<String> {
+    let parts = body.pointer("/candidates/0/content/parts")?.as_array()?;
+    Some(parts.iter().filter_map(|p| p.get("text")?.as_str()).collect())
+}
+
+impl ChatProvider for GeminiProvider {
+    fn request(&self, client: &Client, config: &LlmConfig, messages: &[ChatMessage], options: &ChatOptions) -> RequestBuilder {
+        let contents: Vec<Value> = messages
+            .iter()
+            .filter(|m| m.role != "system")
+            .map(|m| {
+                let role = if m.role == "assistant" { "model" } else { "user" };
+                json!({ "role": role, "parts": [{ "text": m.content }] })
+            })
+            .collect();
+        let mut body = json!({
+            "contents": contents,
+            "generationConfig": { "temperature": options.temperature },
+        });
+        if let Some(schema) = options.json_schema {
+            // Gemini's schema dialect (OpenAPI subset) has no additionalProperties
+            let mut schema = schema.clone();
+            if let Some(object) = schema.as_object_mut() {
+                object.remove("additionalProperties");
+            }
+            body["generationConfig"]["responseMimeType"] = json!("application/json");
+            body["generationConfig"]["responseSchema"] = schema;
+        }
+        if let Some(system) = system_instruction(messages) {
+            body["systemInstruction"] = json!({ "parts": [{ "text": system }] });
+        }
+
+        let url = if options.stream {
+            format!("{}/models/{}:streamGenerateContent?alt=sse", base_url(config), config.model)
+        } else {
+            format!("{}/models/{}:generateContent", base_url(config), config.model)
+        };
+
+        client
+            .post(url)
+            .header("x-goog-api-key", &config.api_key)
+            .header("Content-Type", "application/json")
+            .json(&body)
+    }
+
+    fn parse_response(&self, body: &Value) -> Option<String> {
+        gemini_text(body)
+    }
+
+    fn parse_stream_chunk(&self, chunk: &Value) -> StreamEvent {
+        gemini_text(chunk).map_or(StreamEvent::Skip, StreamEvent::Delta)
+    }
+
+    // Streamed chunks carry running totals
+    fn parse_usage(&self, body: &Value) -> Option<TokenUsage> {
+        usage_fields(body.get("usageMetadata")?, "promptTokenCount", "candidatesTokenCount")
+    }
+
+    fn models_request(&self, client: &Client, config: &LlmConfig) -> Option<RequestBuilder> {
+        Some(
+            client
+                .get(format!("{}/models", base_url(config)))
+                .query(&[("pageSize", "1000")])
+                .header("x-goog-api-key", &config.api_key),
+        )
+    }
+
+    // Only models that can chat, without the "models/" prefix of their resource name
+    fn parse_models(&self, body: &Value) -> Vec<String> {
+        body.get("models")
+            .and_then(|m| m.as_array())
+            .map(|models| {
+                models
+                    .iter()
+                    .filter(|m| {
+                        m.get("supportedGenerationMethods")
+                            .and_then(|methods| methods.as_array())
+                            .is_some_and(|methods| methods.iter().any(|method| method == "generateContent"))
+                    })
+                    .filter_map(|m| m.get("name")?.as_str())
+                    .map(|name| name.trim_start_matches("models/").to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn parse_model(&self, body: &Value) -> Option<String> {
+        body.get("modelVersion")?.as_str().map(|s| s.to_string())
+    }
+
+    fn supports_json_schema(&self) -> bool {
+        true
+    }
+}
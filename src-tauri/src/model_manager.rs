@@ -1,15 +1,31 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::cell::Cell;
 use std::fs::File;
-use std::io::Write;
+use std::rc::Rc;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
 use anyhow::Result;
 use futures_util::StreamExt;
+use reqwest::Client;
 use tar::Archive;
 use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use xz2::read::XzDecoder;
 use tokio_util::sync::CancellationToken;
-use crate::storage::{ModelVersion, ProxyConfig};
+use crate::storage::{ModelMirror, ModelVersion, ProxyConfig};
 use crate::http_client::build_client;
+use serde::Serialize;
 
-/// Get the download URL for a specific model version
+/// Marker file inside a version directory recording when the model was last loaded
+const LAST_USED_FILE: &str = ".last_used";
+
+/// Marker file inside a version directory recording which build was downloaded
+const BUILD_FILE: &str = ".model_build";
+
+/// Timeout for the latency probe sent to each mirror before downloading
+const MIRROR_PROBE_TIMEOUT_SECS: u64 = 5;
+
+/// Get the GitHub release download URL for a specific model version
 pub fn get_model_url(version: &ModelVersion) -> &'static str {
     match version {
         ModelVersion::Quantized => "https://github.com/k2-fsa/sherpa-onnx/releases/download/asr-models/sherpa-onnx-sense-voice-zh-en-ja-ko-yue-int8-2025-09-09.tar.bz2",
@@ -18,7 +34,7 @@ pub fn get_model_url(version: &ModelVersion) -> &'static str {
 }
 
 /// Get the extracted folder name for a specific model version
-fn get_extracted_folder_name(version: &ModelVersion) -> &'static str {
+pub fn get_extracted_folder_name(version: &ModelVersion) -> &'static str {
     match version {
         ModelVersion::Quantized => "sherpa-onnx-sense-voice-zh-en-ja-ko-yue-int8-2025-09-09",
         ModelVersion::Unquantized => "sherpa-onnx-sense-voice-zh-en-ja-ko-yue-2024-07-17",
@@ -52,109 +68,518 @@ pub fn check_model_exists(model_dir: &str) -> bool {
     path.join("model.onnx").exists() && path.join("tokens.txt").exists()
 }
 
-/// Download model for a specific version with cancellation support
-pub async fn download_model_version<F>(
-    base_dir: &str,
-    version: &ModelVersion,
-    proxy: &ProxyConfig,
-    cancel_token: CancellationToken,
-    on_progress: F
-) -> Result<()>
-where F: Fn(u64, u64) + Send + 'static {
-    let url = get_model_url(version);
+/// A specific published build of a model archive
+#[derive(Serialize, Clone, Debug)]
+pub struct ModelBuild {
+    pub name: String, // Archive name without extension, also the extracted folder name
+    pub url: String,
+    pub size_bytes: u64,
+}
+
+/// Get the build pinned for a version in this release of the app
+pub fn get_default_build(version: &ModelVersion) -> ModelBuild {
+    ModelBuild {
+        name: get_extracted_folder_name(version).to_string(),
+        url: get_model_url(version).to_string(),
+        size_bytes: 0,
+    }
+}
+
+/// Get the build name installed for a version, falling back to the pinned build
+pub fn get_installed_build(base_dir: &str, version: &ModelVersion) -> String {
     let version_dir = get_model_dir_for_version(base_dir, version);
-    let target_path = Path::new(&version_dir);
+    std::fs::read_to_string(Path::new(&version_dir).join(BUILD_FILE))
+        .map(|s| s.trim().to_string())
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| get_extracted_folder_name(version).to_string())
+}
 
-    if !target_path.exists() {
-        std::fs::create_dir_all(target_path)?;
+/// Record which build was installed for a version
+fn record_installed_build(target_path: &Path, build_name: &str) {
+    if let Err(e) = std::fs::write(target_path.join(BUILD_FILE), build_name) {
+        eprintln!("Failed to record installed model build: {}", e);
     }
+}
 
-    let client = build_client(proxy, 600)?;
-    let res = client.get(url).send().await?;
+/// Disk usage and usage information for a single model version
+#[derive(Serialize, Clone, Debug)]
+pub struct ModelStorageInfo {
+    pub version: ModelVersion,
+    pub downloaded: bool,
+    pub size_bytes: u64,
+    pub last_used: Option<String>,
+}
+
+/// Record that the model for a version was just loaded
+pub fn mark_model_used(base_dir: &str, version: &ModelVersion) {
+    let version_dir = get_model_dir_for_version(base_dir, version);
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    if let Err(e) = std::fs::write(Path::new(&version_dir).join(LAST_USED_FILE), timestamp) {
+        eprintln!("Failed to record model usage: {}", e);
+    }
+}
+
+/// Get size on disk and last-used time for every known model version
+pub fn get_model_storage_info(base_dir: &str) -> Vec<ModelStorageInfo> {
+    [ModelVersion::Quantized, ModelVersion::Unquantized]
+        .into_iter()
+        .map(|version| {
+            let version_dir = get_model_dir_for_version(base_dir, &version);
+            let path = Path::new(&version_dir);
+            let last_used = std::fs::read_to_string(path.join(LAST_USED_FILE))
+                .ok()
+                .map(|s| s.trim().to_string());
+            ModelStorageInfo {
+                downloaded: check_model_exists_for_version(base_dir, &version),
+                size_bytes: dir_size(path),
+                last_used,
+                version,
+            }
+        })
+        .collect()
+}
+
+/// Delete all files of a downloaded model version
+pub fn delete_model_version(base_dir: &str, version: &ModelVersion) -> Result<()> {
+    let version_dir = get_model_dir_for_version(base_dir, version);
+    let path = Path::new(&version_dir);
+    if !path.exists() {
+        return Err(anyhow::anyhow!("Model version not downloaded"));
+    }
+    std::fs::remove_dir_all(path)?;
+    Ok(())
+}
+
+/// Helper function to compute the total size of a directory, 0 if it doesn't exist
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// A candidate location to download a model archive from
+#[derive(Clone, Debug)]
+pub struct MirrorUrl {
+    pub name: String,
+    pub url: String,
+}
+
+/// Get all download URLs for a build: its published URL plus any configured mirrors
+pub fn get_model_mirror_urls(build: &ModelBuild, mirrors: &[ModelMirror]) -> Vec<MirrorUrl> {
+    let source_name = if build.url.starts_with("https://github.com/") {
+        "GitHub".to_string()
+    } else {
+        reqwest::Url::parse(&build.url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()))
+            .unwrap_or_else(|| "Catalog".to_string())
+    };
+    let mut urls = vec![MirrorUrl {
+        name: source_name,
+        url: build.url.clone(),
+    }];
+
+    let archive_name = format!("{}.tar.bz2", build.name);
+    for mirror in mirrors.iter().filter(|m| m.enabled) {
+        let url = match mirror.urls.get(&build.name) {
+            Some(url) => url.trim().to_string(),
+            None => mirror
+                .url_template
+                .trim()
+                .replace("{name}", &build.name)
+                .replace("{file}", &archive_name),
+        };
+        if url.is_empty() {
+            continue;
+        }
+        urls.push(MirrorUrl {
+            name: mirror.name.clone(),
+            url,
+        });
+    }
+
+    urls
+}
+
+/// Probe a mirror with a HEAD request, returning its latency if reachable
+async fn probe_mirror(client: &Client, mirror: &MirrorUrl) -> Option<Duration> {
+    let start = Instant::now();
+    let res = client
+        .head(&mirror.url)
+        .timeout(Duration::from_secs(MIRROR_PROBE_TIMEOUT_SECS))
+        .send()
+        .await
+        .ok()?;
+    if res.status().is_success() {
+        Some(start.elapsed())
+    } else {
+        None
+    }
+}
+
+/// Order mirrors by probe latency; unreachable mirrors are kept last as a final resort
+async fn rank_mirrors(client: &Client, mirrors: Vec<MirrorUrl>) -> Vec<MirrorUrl> {
+    let latencies = futures_util::future::join_all(mirrors.iter().map(|m| probe_mirror(client, m))).await;
+
+    let mut ranked: Vec<(Option<Duration>, MirrorUrl)> = latencies.into_iter().zip(mirrors).collect();
+    for (latency, mirror) in &ranked {
+        match latency {
+            Some(latency) => println!("Mirror {} reachable in {} ms", mirror.name, latency.as_millis()),
+            None => println!("Mirror {} unreachable", mirror.name),
+        }
+    }
+    ranked.sort_by_key(|(latency, _)| latency.unwrap_or(Duration::MAX));
+    ranked.into_iter().map(|(_, mirror)| mirror).collect()
+}
+
+/// Phase of a model download, reported with every progress event
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadPhase {
+    Connecting,
+    Downloading,
+    Verifying,
+    Extracting,
+    Loading,
+}
+
+/// Structured progress for a model download.
+/// While downloading, current/total are bytes received; while extracting, they are
+/// compressed archive bytes consumed, with the number of extracted entries alongside.
+#[derive(Serialize, Clone, Debug)]
+pub struct DownloadProgress {
+    pub phase: DownloadPhase,
+    pub current: u64,
+    pub total: u64,
+    pub bytes_per_sec: u64,
+    pub eta_secs: Option<u64>,
+    pub mirror: String,
+    pub entries_extracted: u64,
+}
+
+impl DownloadProgress {
+    pub fn new(phase: DownloadPhase) -> Self {
+        Self {
+            phase,
+            current: 0,
+            total: 0,
+            bytes_per_sec: 0,
+            eta_secs: None,
+            mirror: String::new(),
+            entries_extracted: 0,
+        }
+    }
+}
+
+/// Errors from the model download pipeline
+#[derive(Debug, thiserror::Error)]
+pub enum DownloadError {
+    #[error("Download cancelled")]
+    Cancelled,
+    #[error("Network error: {0}")]
+    Network(#[from] reqwest::Error),
+    #[error("Incomplete download: expected {expected} bytes, got {actual}")]
+    Incomplete { expected: u64, actual: u64 },
+    #[error("Extraction failed: {0}")]
+    Extract(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Other(#[from] anyhow::Error),
+}
+
+/// Minimum interval between two download progress events
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Window over which the download speed is measured
+const SPEED_WINDOW: Duration = Duration::from_secs(1);
+
+/// Tracks download speed over a short sliding window and throttles progress events
+struct SpeedTracker {
+    window_start: Instant,
+    window_bytes: u64,
+    bytes_per_sec: u64,
+    last_emit: Option<Instant>,
+}
+
+impl SpeedTracker {
+    fn new() -> Self {
+        Self {
+            window_start: Instant::now(),
+            window_bytes: 0,
+            bytes_per_sec: 0,
+            last_emit: None,
+        }
+    }
+
+    /// Record received bytes and return the current speed in bytes/sec
+    fn record(&mut self, bytes: u64) -> u64 {
+        self.window_bytes += bytes;
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= SPEED_WINDOW || self.bytes_per_sec == 0 {
+            let secs = elapsed.as_secs_f64().max(0.001);
+            self.bytes_per_sec = (self.window_bytes as f64 / secs) as u64;
+            if elapsed >= SPEED_WINDOW {
+                self.window_start = Instant::now();
+                self.window_bytes = 0;
+            }
+        }
+        self.bytes_per_sec
+    }
+
+    /// Whether enough time has passed since the last emitted event
+    fn should_emit(&mut self) -> bool {
+        if self.last_emit.is_some_and(|t| t.elapsed() < PROGRESS_EMIT_INTERVAL) {
+            return false;
+        }
+        self.last_emit = Some(Instant::now());
+        true
+    }
+}
+
+/// Download an archive from the fastest mirror, failing over to the next one on error.
+/// Returns the name of the mirror the archive was downloaded from.
+async fn download_with_failover<F>(
+    client: &Client,
+    mirrors: Vec<MirrorUrl>,
+    dest_path: &Path,
+    cancel_token: &CancellationToken,
+    on_progress: &F
+) -> Result<String, DownloadError>
+where F: Fn(DownloadProgress) {
+    on_progress(DownloadProgress::new(DownloadPhase::Connecting));
+
+    let mut last_error = None;
+
+    for mirror in rank_mirrors(client, mirrors).await {
+        println!("Downloading model from {}: {}", mirror.name, mirror.url);
+        match download_from_mirror(client, &mirror, dest_path, cancel_token, on_progress).await {
+            Ok(()) => return Ok(mirror.name),
+            Err(DownloadError::Cancelled) => return Err(DownloadError::Cancelled),
+            Err(e) => {
+                eprintln!("Download from mirror {} failed: {}", mirror.name, e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No download mirrors available").into()))
+}
+
+/// Download an archive from a single mirror, removing the partial file on failure
+async fn download_from_mirror<F>(
+    client: &Client,
+    mirror: &MirrorUrl,
+    dest_path: &Path,
+    cancel_token: &CancellationToken,
+    on_progress: &F
+) -> Result<(), DownloadError>
+where F: Fn(DownloadProgress) {
+    let res = tokio::select! {
+        _ = cancel_token.cancelled() => return Err(DownloadError::Cancelled),
+        res = client.get(&mirror.url).send() => res?.error_for_status()?,
+    };
     let total_size = res.content_length().unwrap_or(0);
 
     let mut stream = res.bytes_stream();
-    let temp_tar_path = target_path.join("model.tar.bz2");
-    let mut file = File::create(&temp_tar_path)?;
+    let mut file = File::create(dest_path)?;
     let mut downloaded: u64 = 0;
+    let mut tracker = SpeedTracker::new();
 
-    loop {
+    let result = loop {
         tokio::select! {
-            _ = cancel_token.cancelled() => {
-                // Clean up partial download
-                drop(file);
-                let _ = std::fs::remove_file(&temp_tar_path);
-                return Err(anyhow::anyhow!("Download cancelled"));
-            }
+            _ = cancel_token.cancelled() => break Err(DownloadError::Cancelled),
             chunk = stream.next() => {
                 match chunk {
                     Some(Ok(data)) => {
-                        file.write_all(&data)?;
+                        if let Err(e) = file.write_all(&data) {
+                            break Err(e.into());
+                        }
                         downloaded += data.len() as u64;
-                        on_progress(downloaded, total_size);
-                    }
-                    Some(Err(e)) => {
-                        drop(file);
-                        let _ = std::fs::remove_file(&temp_tar_path);
-                        return Err(e.into());
+                        let bytes_per_sec = tracker.record(data.len() as u64);
+                        if tracker.should_emit() || downloaded == total_size {
+                            let eta_secs = (total_size > 0 && bytes_per_sec > 0)
+                                .then(|| total_size.saturating_sub(downloaded) / bytes_per_sec);
+                            on_progress(DownloadProgress {
+                                current: downloaded,
+                                total: total_size,
+                                bytes_per_sec,
+                                eta_secs,
+                                mirror: mirror.name.clone(),
+                                ..DownloadProgress::new(DownloadPhase::Downloading)
+                            });
+                        }
                     }
-                    None => break, // Stream finished
+                    Some(Err(e)) => break Err(e.into()),
+                    None => break Ok(()), // Stream finished
                 }
             }
         }
+    };
+
+    // Verify we received the whole archive before handing it to the extractor
+    let result = result.and_then(|_| {
+        on_progress(DownloadProgress {
+            current: downloaded,
+            total: total_size,
+            mirror: mirror.name.clone(),
+            ..DownloadProgress::new(DownloadPhase::Verifying)
+        });
+        file.flush()?;
+        if total_size > 0 && downloaded != total_size {
+            return Err(DownloadError::Incomplete { expected: total_size, actual: downloaded });
+        }
+        detect_archive_format(dest_path)?;
+        Ok(())
+    });
+
+    if result.is_err() {
+        // Clean up partial download
+        drop(file);
+        let _ = std::fs::remove_file(dest_path);
     }
 
-    // Extract
+    result
+}
+
+/// Extract a downloaded archive into target_path, reporting extraction progress
+fn extract_downloaded_archive<F>(archive_path: &Path, target_path: &Path, mirror_name: &str, on_progress: &F) -> Result<(), DownloadError>
+where F: Fn(DownloadProgress) {
     println!("Extracting model...");
-    let tar_bz2 = File::open(&temp_tar_path)?;
-    let tar = BzDecoder::new(tar_bz2);
-    let mut archive = Archive::new(tar);
-    archive.unpack(target_path)?;
+    let mut tracker = SpeedTracker::new();
+    extract_archive(archive_path, target_path, |consumed, total, entries| {
+        // Archives have thousands of small entries; one event per entry floods the UI
+        if !tracker.should_emit() && consumed < total {
+            return;
+        }
+        on_progress(DownloadProgress {
+            current: consumed,
+            total,
+            mirror: mirror_name.to_string(),
+            entries_extracted: entries,
+            ..DownloadProgress::new(DownloadPhase::Extracting)
+        });
+    })
+    .map_err(|e| DownloadError::Extract(e.to_string()))
+}
 
-    // Cleanup temp file
+/// Download model for a specific version with cancellation support.
+/// The build is downloaded and extracted into a sibling staging directory and only replaces
+/// the version directory once it is complete, so a loaded model is never overwritten in place.
+pub async fn download_model_version<F>(
+    base_dir: &str,
+    version: &ModelVersion,
+    build: &ModelBuild,
+    mirrors: &[ModelMirror],
+    proxy: &ProxyConfig,
+    cancel_token: CancellationToken,
+    on_progress: F
+) -> Result<(), DownloadError>
+where F: Fn(DownloadProgress) + Send + Sync + 'static {
+    let version_dir = get_model_dir_for_version(base_dir, version);
+    let target_path = Path::new(&version_dir);
+    let staging_path = sibling_dir(target_path, "staging");
+
+    // Leftovers of an interrupted download
+    if staging_path.exists() {
+        std::fs::remove_dir_all(&staging_path)?;
+    }
+    std::fs::create_dir_all(&staging_path)?;
+
+    let result = download_into(&staging_path, build, mirrors, proxy, &cancel_token, &on_progress).await;
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(&staging_path);
+    }
+    result?;
+
+    replace_dir(&staging_path, target_path)?;
+    Ok(())
+}
+
+/// Download and extract a build into staging_path, then verify the model files are there
+async fn download_into<F>(
+    staging_path: &Path,
+    build: &ModelBuild,
+    mirrors: &[ModelMirror],
+    proxy: &ProxyConfig,
+    cancel_token: &CancellationToken,
+    on_progress: &F
+) -> Result<(), DownloadError>
+where F: Fn(DownloadProgress) {
+    let client = build_client(proxy, 600)?;
+    let temp_tar_path = staging_path.join("model.tar.bz2");
+    let urls = get_model_mirror_urls(build, mirrors);
+    let mirror_name = download_with_failover(&client, urls, &temp_tar_path, cancel_token, on_progress).await?;
+
+    extract_downloaded_archive(&temp_tar_path, staging_path, &mirror_name, on_progress)?;
     std::fs::remove_file(temp_tar_path)?;
 
     // Handle nested folder structure
-    let extracted_folder_name = get_extracted_folder_name(version);
-    let nested_dir = target_path.join(extracted_folder_name);
+    let nested_dir = staging_path.join(&build.name);
     if nested_dir.exists() {
-        move_files_from_nested(&nested_dir, target_path)?;
+        move_files_from_nested(&nested_dir, staging_path)?;
     }
 
+    if !staging_path.join("model.onnx").exists() || !staging_path.join("tokens.txt").exists() {
+        return Err(DownloadError::Extract("archive doesn't contain model.onnx and tokens.txt".to_string()));
+    }
+    record_installed_build(staging_path, &build.name);
+    Ok(())
+}
+
+/// Path of a sibling directory of `dir`, e.g. "quantized.staging"
+fn sibling_dir(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(format!(".{}", suffix));
+    dir.with_file_name(name)
+}
+
+/// Move `source` into place as `target`, keeping the old target until the move succeeded
+fn replace_dir(source: &Path, target: &Path) -> std::io::Result<()> {
+    if !target.exists() {
+        return std::fs::rename(source, target);
+    }
+
+    let old_path = sibling_dir(target, "old");
+    if old_path.exists() {
+        std::fs::remove_dir_all(&old_path)?;
+    }
+    std::fs::rename(target, &old_path)?;
+    if let Err(e) = std::fs::rename(source, target) {
+        // Put the previous model back so the version keeps working
+        let _ = std::fs::rename(&old_path, target);
+        return Err(e);
+    }
+    if let Err(e) = std::fs::remove_dir_all(&old_path) {
+        eprintln!("Failed to remove previous model files: {}", e);
+    }
     Ok(())
 }
 
 /// Legacy download function (downloads quantized by default)
-pub async fn download_model<F>(model_dir: &str, proxy: &ProxyConfig, on_progress: F) -> Result<()>
-where F: Fn(u64, u64) + Send + 'static {
+pub async fn download_model<F>(model_dir: &str, mirrors: &[ModelMirror], proxy: &ProxyConfig, on_progress: F) -> Result<(), DownloadError>
+where F: Fn(DownloadProgress) + Send + Sync + 'static {
     // For backwards compatibility, download to model_dir directly
     let target_path = Path::new(model_dir);
     if !target_path.exists() {
         std::fs::create_dir_all(target_path)?;
     }
 
-    let url = get_model_url(&ModelVersion::Quantized);
     let client = build_client(proxy, 600)?;
-    let res = client.get(url).send().await?;
-    let total_size = res.content_length().unwrap_or(0);
-
-    let mut stream = res.bytes_stream();
     let temp_tar_path = target_path.join("model.tar.bz2");
-    let mut file = File::create(&temp_tar_path)?;
-    let mut downloaded: u64 = 0;
-
-    while let Some(item) = stream.next().await {
-        let chunk = item?;
-        file.write_all(&chunk)?;
-        downloaded += chunk.len() as u64;
-        on_progress(downloaded, total_size);
-    }
+    let urls = get_model_mirror_urls(&get_default_build(&ModelVersion::Quantized), mirrors);
+    let mirror_name = download_with_failover(&client, urls, &temp_tar_path, &CancellationToken::new(), &on_progress).await?;
 
-    println!("Extracting model...");
-    let tar_bz2 = File::open(&temp_tar_path)?;
-    let tar = BzDecoder::new(tar_bz2);
-    let mut archive = Archive::new(tar);
-    archive.unpack(target_path)?;
+    extract_downloaded_archive(&temp_tar_path, target_path, &mirror_name, &on_progress)?;
 
     std::fs::remove_file(temp_tar_path)?;
 
@@ -167,21 +592,177 @@ where F: Fn(u64, u64) + Send + 'static {
     Ok(())
 }
 
-/// Import model from a local tar.bz2 file
+/// Supported model archive formats, detected from magic bytes rather than the file extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    TarBz2,
+    TarGz,
+    TarXz,
+    Zip,
+}
+
+/// Detect the archive format of a file by reading its magic bytes
+pub fn detect_archive_format(archive_path: &Path) -> Result<ArchiveFormat> {
+    let mut header = [0u8; 6];
+    let mut file = File::open(archive_path)?;
+    let read = file.read(&mut header)?;
+    let header = &header[..read];
+
+    if header.starts_with(b"BZh") {
+        Ok(ArchiveFormat::TarBz2)
+    } else if header.starts_with(&[0x1f, 0x8b]) {
+        Ok(ArchiveFormat::TarGz)
+    } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        Ok(ArchiveFormat::TarXz)
+    } else if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+        Ok(ArchiveFormat::Zip)
+    } else {
+        Err(anyhow::anyhow!("Unsupported archive format: expected .tar.bz2, .tar.gz, .tar.xz or .zip"))
+    }
+}
+
+/// Reader wrapper counting the bytes consumed from the underlying archive file
+struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+/// Extract a model archive into target_path, whatever its format.
+/// on_entry receives (compressed bytes consumed, archive size, entries extracted) after each entry.
+fn extract_archive<P>(archive_path: &Path, target_path: &Path, mut on_entry: P) -> Result<()>
+where P: FnMut(u64, u64, u64) {
+    let format = detect_archive_format(archive_path)?;
+    let file = File::open(archive_path)?;
+    let archive_size = file.metadata()?.len();
+    let consumed = Rc::new(Cell::new(0u64));
+    let reader = CountingReader { inner: file, count: consumed.clone() };
+
+    let mut entries: u64 = 0;
+    // Zip needs random access, so its progress is counted from entry sizes instead
+    let mut zip_consumed: u64 = 0;
+    match format {
+        ArchiveFormat::TarBz2 => unpack_tar(Archive::new(BzDecoder::new(reader)), target_path, |n| {
+            entries = n;
+            on_entry(consumed.get(), archive_size, n);
+        })?,
+        ArchiveFormat::TarGz => unpack_tar(Archive::new(GzDecoder::new(reader)), target_path, |n| {
+            entries = n;
+            on_entry(consumed.get(), archive_size, n);
+        })?,
+        ArchiveFormat::TarXz => unpack_tar(Archive::new(XzDecoder::new(reader)), target_path, |n| {
+            entries = n;
+            on_entry(consumed.get(), archive_size, n);
+        })?,
+        ArchiveFormat::Zip => {
+            let mut zip = zip::ZipArchive::new(reader.inner)?;
+            for i in 0..zip.len() {
+                let mut entry = zip.by_index(i)?;
+                // Skip entries that would escape the target directory
+                let Some(relative_path) = entry.enclosed_name() else {
+                    continue;
+                };
+                let out_path = target_path.join(relative_path);
+                if entry.is_dir() {
+                    std::fs::create_dir_all(&out_path)?;
+                } else {
+                    if let Some(parent) = out_path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    let mut out_file = File::create(&out_path)?;
+                    std::io::copy(&mut entry, &mut out_file)?;
+                }
+                entries += 1;
+                zip_consumed += entry.compressed_size();
+                on_entry(zip_consumed.min(archive_size), archive_size, entries);
+            }
+        }
+    }
+
+    println!("Extracted {} entries", entries);
+    Ok(())
+}
+
+/// Helper function to unpack a tar archive entry by entry
+fn unpack_tar<R: Read, P: FnMut(u64)>(mut archive: Archive<R>, target_path: &Path, mut on_entry: P) -> Result<()> {
+    let mut count: u64 = 0;
+    for entry in archive.entries()? {
+        entry?.unpack_in(target_path)?;
+        count += 1;
+        on_entry(count);
+    }
+    Ok(())
+}
+
+/// Import model from a local archive (.tar.bz2, .tar.gz, .tar.xz, .zip) or a plain folder
 pub fn import_model_from_file(archive_path: &str, base_dir: &str, version: &ModelVersion) -> Result<()> {
+    let source_path = Path::new(archive_path);
+    if source_path.is_dir() {
+        return import_model_from_folder(archive_path, base_dir, version);
+    }
+
+    let version_dir = get_model_dir_for_version(base_dir, version);
+    import_into_staging(Path::new(&version_dir), |staging_path| {
+        println!("Extracting model from: {}", archive_path);
+        extract_archive(source_path, staging_path, |_, _, _| {})
+    })
+}
+
+/// Import model from a folder containing loose model files (e.g. a Hugging Face checkout)
+pub fn import_model_from_folder(folder_path: &str, base_dir: &str, version: &ModelVersion) -> Result<()> {
+    let source_path = Path::new(folder_path);
+    if !source_path.is_dir() {
+        return Err(anyhow::anyhow!("Not a folder: {}", folder_path));
+    }
+
     let version_dir = get_model_dir_for_version(base_dir, version);
     let target_path = Path::new(&version_dir);
 
-    if !target_path.exists() {
-        std::fs::create_dir_all(target_path)?;
+    // Copying the version directory onto itself would truncate the files being read
+    if let (Ok(source), Ok(target)) = (source_path.canonicalize(), target_path.canonicalize()) {
+        if source.starts_with(&target) {
+            return Err(anyhow::anyhow!("The folder is already the model directory for this version"));
+        }
     }
 
-    // Extract the archive
-    println!("Extracting model from: {}", archive_path);
-    let tar_bz2 = File::open(archive_path)?;
-    let tar = BzDecoder::new(tar_bz2);
-    let mut archive = Archive::new(tar);
-    archive.unpack(target_path)?;
+    // Copy rather than move so the user's folder is left untouched
+    import_into_staging(target_path, |staging_path| {
+        println!("Copying model from folder: {}", folder_path);
+        copy_model_files(source_path, staging_path, version)
+    })
+}
+
+/// Build an import in a sibling staging directory and only replace the version directory
+/// once the model files verified, so a failed import leaves the installed model intact
+fn import_into_staging<F>(target_path: &Path, fill: F) -> Result<()>
+where F: FnOnce(&Path) -> Result<()> {
+    let staging_path = sibling_dir(target_path, "staging");
+    if staging_path.exists() {
+        std::fs::remove_dir_all(&staging_path)?;
+    }
+    std::fs::create_dir_all(&staging_path)?;
+
+    let result = fill(&staging_path).and_then(|_| finalize_imported_model(&staging_path));
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(&staging_path);
+    }
+    result?;
+
+    replace_dir(&staging_path, target_path)?;
+    Ok(())
+}
+
+/// Flatten nested folders left by an import and verify the required model files exist
+fn finalize_imported_model(target_path: &Path) -> Result<()> {
+    // The build of an imported model is unknown, so forget any previously downloaded one
+    let _ = std::fs::remove_file(target_path.join(BUILD_FILE));
 
     // Handle nested folder structure - look for any folder containing model files
     // First try the known folder names
@@ -219,6 +800,11 @@ pub fn import_model_from_file(archive_path: &str, base_dir: &str, version: &Mode
         }
     }
 
+    // Loose files copied from a folder may still carry the quantized file name
+    if !target_path.join("model.onnx").exists() && target_path.join("model.int8.onnx").exists() {
+        std::fs::rename(target_path.join("model.int8.onnx"), target_path.join("model.onnx"))?;
+    }
+
     // Verify the model files exist
     if !target_path.join("model.onnx").exists() || !target_path.join("tokens.txt").exists() {
         return Err(anyhow::anyhow!("Invalid model archive: missing model.onnx or tokens.txt"));
@@ -227,6 +813,40 @@ pub fn import_model_from_file(archive_path: &str, base_dir: &str, version: &Mode
     Ok(())
 }
 
+/// Model file names to take from an imported folder for a version, in order of preference
+fn model_file_candidates(version: &ModelVersion) -> [&'static str; 2] {
+    match version {
+        ModelVersion::Quantized => ["model.int8.onnx", "model.onnx"],
+        ModelVersion::Unquantized => ["model.onnx", "model.int8.onnx"],
+    }
+}
+
+/// Copy only the files the version needs from a folder (or its model subfolder),
+/// leaving out VCS data, other builds and anything else a checkout carries
+fn copy_model_files(source: &Path, target: &Path, version: &ModelVersion) -> Result<()> {
+    let source = if source.join("tokens.txt").is_file() {
+        source.to_path_buf()
+    } else {
+        // The user may have picked the folder an archive was extracted into
+        std::fs::read_dir(source)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|path| !path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')))
+            .find(|path| path.join("tokens.txt").is_file())
+            .ok_or_else(|| anyhow::anyhow!("No tokens.txt found in {}", source.display()))?
+    };
+
+    let model_file = model_file_candidates(version)
+        .into_iter()
+        .map(|name| source.join(name))
+        .find(|path| path.is_file())
+        .ok_or_else(|| anyhow::anyhow!("No model.onnx or model.int8.onnx found in {}", source.display()))?;
+
+    std::fs::copy(model_file, target.join("model.onnx"))?;
+    std::fs::copy(source.join("tokens.txt"), target.join("tokens.txt"))?;
+    Ok(())
+}
+
 /// Helper function to move files from nested directory to target
 fn move_files_from_nested(nested_dir: &Path, target_path: &Path) -> Result<()> {
     for entry in std::fs::read_dir(nested_dir)? {
@@ -0,0 +1,128 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use crate::storage::{ModelUpdateConfig, ModelVersion, ProxyConfig};
+use crate::http_client::build_client;
+use crate::model_manager::{self, ModelBuild};
+
+/// GitHub release that hosts the sherpa-onnx ASR model archives
+const DEFAULT_CATALOG_URL: &str = "https://api.github.com/repos/k2-fsa/sherpa-onnx/releases/tags/asr-models";
+
+/// Release catalog in the GitHub release API format; self-hosted catalogs use the same shape
+#[derive(Deserialize)]
+struct CatalogRelease {
+    #[serde(default)]
+    body: Option<String>,
+    assets: Vec<CatalogAsset>,
+}
+
+#[derive(Deserialize)]
+struct CatalogAsset {
+    name: String,
+    size: u64,
+    browser_download_url: String,
+}
+
+/// A newer build available for an installed model version
+#[derive(Serialize, Clone, Debug)]
+pub struct ModelUpdateInfo {
+    pub version: ModelVersion,
+    pub current_build: String,
+    pub latest: ModelBuild,
+    pub changelog: String,
+}
+
+/// Split a build name like "sherpa-onnx-sense-voice-zh-en-ja-ko-yue-int8-2025-09-09"
+/// into its family ("...-int8") and its release date ("2025-09-09")
+fn split_build_name(name: &str) -> Option<(&str, &str)> {
+    if name.len() < 12 || !name.is_char_boundary(name.len() - 11) {
+        return None;
+    }
+    let (family, date) = name.split_at(name.len() - 11);
+    let date = date.strip_prefix('-')?;
+    let is_date = date.char_indices().all(|(i, c)| match i {
+        4 | 7 => c == '-',
+        _ => c.is_ascii_digit(),
+    });
+    is_date.then_some((family, date))
+}
+
+/// Fetch the model catalog
+async fn fetch_catalog(catalog_url: &str, proxy: &ProxyConfig) -> Result<CatalogRelease> {
+    let url = if catalog_url.trim().is_empty() {
+        DEFAULT_CATALOG_URL
+    } else {
+        catalog_url.trim()
+    };
+
+    let client = build_client(proxy, 30)?;
+    let response = client
+        .get(url)
+        .header("User-Agent", "fastsp")
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Model catalog error ({}): {}", status, error_text));
+    }
+
+    Ok(response.json().await?)
+}
+
+/// Find the newest build in the catalog of the same family as the installed one
+fn find_newer_build(catalog: &CatalogRelease, installed_build: &str) -> Option<ModelBuild> {
+    let (family, installed_date) = split_build_name(installed_build)?;
+
+    catalog
+        .assets
+        .iter()
+        .filter_map(|asset| {
+            let name = asset.name.strip_suffix(".tar.bz2")?;
+            let (asset_family, date) = split_build_name(name)?;
+            (asset_family == family && date > installed_date).then_some((date, asset, name))
+        })
+        .max_by(|a, b| a.0.cmp(b.0))
+        .map(|(_, asset, name)| ModelBuild {
+            name: name.to_string(),
+            url: asset.browser_download_url.clone(),
+            size_bytes: asset.size,
+        })
+}
+
+/// Check the catalog for newer builds of every installed model version
+pub async fn check_for_updates(base_dir: &str, config: &ModelUpdateConfig, proxy: &ProxyConfig) -> Result<Vec<ModelUpdateInfo>> {
+    let installed: Vec<ModelVersion> = [ModelVersion::Quantized, ModelVersion::Unquantized]
+        .into_iter()
+        .filter(|v| model_manager::check_model_exists_for_version(base_dir, v))
+        .collect();
+    if installed.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let catalog = fetch_catalog(&config.catalog_url, proxy).await?;
+    let changelog = catalog.body.clone().unwrap_or_default();
+
+    Ok(installed
+        .into_iter()
+        .filter_map(|version| {
+            let current_build = model_manager::get_installed_build(base_dir, &version);
+            let latest = find_newer_build(&catalog, &current_build)?;
+            Some(ModelUpdateInfo {
+                version,
+                current_build,
+                latest,
+                changelog: changelog.clone(),
+            })
+        })
+        .collect())
+}
+
+/// Get the newest catalog build of a version's family, if it is newer than the installed one
+pub async fn get_update_build(base_dir: &str, version: &ModelVersion, config: &ModelUpdateConfig, proxy: &ProxyConfig) -> Result<ModelBuild> {
+    let catalog = fetch_catalog(&config.catalog_url, proxy).await?;
+    let current_build = model_manager::get_installed_build(base_dir, version);
+    find_newer_build(&catalog, &current_build)
+        .ok_or_else(|| anyhow!("No newer build available for {}", current_build))
+}
@@ -0,0 +1,28 @@
+/// Writing system of a character, as far as dictation text cares
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Script {
+    Han,
+    Kana,
+    Hangul,
+    Latin,
+    Cyrillic,
+    Other,
+}
+
+impl Script {
+    pub fn of(ch: char) -> Script {
+        match ch as u32 {
+            0x3040..=0x30ff | 0x31f0..=0x31ff => Script::Kana,
+            0x4e00..=0x9fff | 0x3400..=0x4dbf | 0xf900..=0xfaff | 0x20000..=0x2ffff => Script::Han,
+            0xac00..=0xd7af | 0x1100..=0x11ff | 0x3130..=0x318f => Script::Hangul,
+            0x0400..=0x04ff => Script::Cyrillic,
+            _ if ch.is_ascii_alphabetic() || ('\u{c0}'..='\u{24f}').contains(&ch) => Script::Latin,
+            _ => Script::Other,
+        }
+    }
+
+    /// CJK scripts, where a single character is roughly a word and a token
+    pub fn is_cjk(self) -> bool {
+        matches!(self, Script::Han | Script::Kana | Script::Hangul)
+    }
+}
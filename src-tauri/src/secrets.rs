@@ -0,0 +1,260 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use crate::storage::{read_recovering, write_atomic, AppConfig, LlmConfig};
+
+/// Service name secrets are filed under in the OS keyring
+const KEYRING_SERVICE: &str = "fastsp";
+
+/// Prefix of config values that refer to a stored secret instead of holding it
+pub const SECRET_REF_PREFIX: &str = "keyring:";
+
+/// Encrypted secrets, used when no OS keyring is available (e.g. Linux without a Secret Service)
+const FALLBACK_FILE: &str = "secrets.enc";
+const FALLBACK_KEY_FILE: &str = "secrets.key";
+
+const NONCE_LEN: usize = 12;
+
+/// Placeholder that replaces secrets in messages
+const REDACTED: &str = "***";
+
+/// The stored secret id a config value refers to, if it is a reference
+fn reference_id(value: &str) -> Option<&str> {
+    value.strip_prefix(SECRET_REF_PREFIX)
+}
+
+/// The API key fields of an LLM config: the primary endpoint, then each fallback endpoint
+fn api_keys(config: &LlmConfig) -> impl Iterator<Item = &String> {
+    std::iter::once(&config.api_key).chain(config.fallback_endpoints.iter().map(|e| &e.api_key))
+}
+
+fn api_keys_mut(config: &mut LlmConfig) -> impl Iterator<Item = &mut String> {
+    std::iter::once(&mut config.api_key).chain(config.fallback_endpoints.iter_mut().map(|e| &mut e.api_key))
+}
+
+/// (base URL, API key) of each endpoint of an LLM config
+fn endpoints(config: &LlmConfig) -> impl Iterator<Item = (&str, &String)> {
+    std::iter::once((config.base_url.as_str(), &config.api_key))
+        .chain(config.fallback_endpoints.iter().map(|e| (e.base_url.as_str(), &e.api_key)))
+}
+
+fn endpoints_mut(config: &mut LlmConfig) -> impl Iterator<Item = (&str, &mut String)> {
+    std::iter::once((config.base_url.as_str(), &mut config.api_key))
+        .chain(config.fallback_endpoints.iter_mut().map(|e| (e.base_url.as_str(), &mut e.api_key)))
+}
+
+/// Create a file only the current user can read; it never exists with wider permissions
+pub fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// A copy of an LLM config without its API keys, for sharing it
+pub fn without_api_keys(config: &LlmConfig) -> LlmConfig {
+    let mut stripped = config.clone();
+    api_keys_mut(&mut stripped).for_each(|key| key.clear());
+    stripped
+}
+
+/// Whether any API key is still stored in plaintext
+pub fn has_plaintext_keys(config: &LlmConfig) -> bool {
+    api_keys(config).any(|key| !key.is_empty() && reference_id(key).is_none())
+}
+
+/// Ids of the stored secrets an LLM config refers to
+pub fn referenced_ids(config: &LlmConfig) -> Vec<String> {
+    api_keys(config).filter_map(|key| reference_id(key)).map(|id| id.to_string()).collect()
+}
+
+/// Replace every occurrence of the given secrets in a message
+pub fn redact(text: &str, secrets: &[&str]) -> String {
+    secrets
+        .iter()
+        .map(|secret| secret.trim())
+        // Very short values would redact ordinary words
+        .filter(|secret| secret.len() >= 4)
+        .fold(text.to_string(), |text, secret| text.replace(secret, REDACTED))
+}
+
+/// Secrets kept in the OS keyring, or in an encrypted file next to the config when there is none
+pub struct SecretStore {
+    dir: PathBuf,
+}
+
+impl SecretStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn get(&self, id: &str) -> Result<String> {
+        match keyring::Entry::new(KEYRING_SERVICE, id).and_then(|entry| entry.get_password()) {
+            Ok(secret) => Ok(secret),
+            Err(_) => self
+                .load_fallback()?
+                .remove(id)
+                .ok_or_else(|| anyhow!("Secret {} not found", id)),
+        }
+    }
+
+    pub fn set(&self, id: &str, secret: &str) -> Result<()> {
+        match keyring::Entry::new(KEYRING_SERVICE, id).and_then(|entry| entry.set_password(secret)) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                eprintln!("[SECRETS] OS keyring unavailable ({}), using encrypted file", e);
+                let mut secrets = self.load_fallback()?;
+                secrets.insert(id.to_string(), secret.to_string());
+                self.save_fallback(&secrets)
+            }
+        }
+    }
+
+    pub fn delete(&self, id: &str) {
+        if let Ok(entry) = keyring::Entry::new(KEYRING_SERVICE, id) {
+            entry.delete_credential().ok();
+        }
+        if let Ok(mut secrets) = self.load_fallback() {
+            if secrets.remove(id).is_some() {
+                self.save_fallback(&secrets).ok();
+            }
+        }
+    }
+
+    /// Move plaintext API keys into the store, leaving references in the config
+    pub fn store_api_keys(&self, config: &mut LlmConfig) -> Result<()> {
+        for key in api_keys_mut(config) {
+            if key.is_empty() || reference_id(key).is_some() {
+                continue;
+            }
+            let id = format!("llm-api-key-{}", uuid::Uuid::new_v4());
+            self.set(&id, key.trim())?;
+            *key = format!("{}{}", SECRET_REF_PREFIX, id);
+        }
+        Ok(())
+    }
+
+    /// A copy of the config with references replaced by the stored API keys.
+    /// Keys that can't be read become empty, so the endpoint fails instead of sending the reference.
+    pub fn resolve_api_keys(&self, config: &LlmConfig) -> LlmConfig {
+        let mut resolved = config.clone();
+        for key in api_keys_mut(&mut resolved) {
+            if let Some(id) = reference_id(key) {
+                *key = self.get(id).unwrap_or_else(|e| {
+                    eprintln!("[SECRETS] Failed to read API key: {}", e);
+                    String::new()
+                });
+            }
+        }
+        resolved
+    }
+
+    /// Prepare the API keys of an LLM config from another machine: references to secrets this
+    /// machine doesn't have are dropped, and missing keys are taken from the current endpoint
+    /// with the same base URL
+    pub fn import_api_keys(&self, imported: &mut LlmConfig, current: &AppConfig) {
+        for (base_url, key) in endpoints_mut(imported) {
+            if reference_id(key).is_some_and(|id| self.get(id).is_err()) {
+                key.clear();
+            }
+            if key.is_empty() {
+                if let Some((_, current_key)) = current
+                    .llm_configs()
+                    .flat_map(endpoints)
+                    .find(|(url, current_key)| *url == base_url && !current_key.is_empty())
+                {
+                    *key = current_key.clone();
+                }
+            }
+        }
+    }
+
+    fn fallback_cipher(&self) -> Result<ChaCha20Poly1305> {
+        let key_path = self.dir.join(FALLBACK_KEY_FILE);
+        let key = match fs::read(&key_path) {
+            Ok(key) if key.len() == 32 => key,
+            _ => {
+                // Secrets encrypted with the lost key can't be read anymore; keep them aside
+                // instead of failing every later write
+                let secrets_path = self.dir.join(FALLBACK_FILE);
+                if secrets_path.exists() {
+                    let unreadable_path = self.dir.join(format!(
+                        "{}.unreadable-{}",
+                        FALLBACK_FILE,
+                        chrono::Local::now().format("%Y%m%d-%H%M%S")
+                    ));
+                    fs::rename(&secrets_path, &unreadable_path)?;
+                    eprintln!(
+                        "[SECRETS] {} is missing or invalid; stored API keys are lost and must be entered again (old secrets kept as {})",
+                        FALLBACK_KEY_FILE,
+                        unreadable_path.display()
+                    );
+                }
+
+                let key = ChaCha20Poly1305::generate_key(&mut OsRng).to_vec();
+                write_private(&key_path, &key)?;
+                key
+            }
+        };
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+
+    fn load_fallback(&self) -> Result<HashMap<String, String>> {
+        let path = self.dir.join(FALLBACK_FILE);
+        if !path.exists() {
+            return Ok(HashMap::new());
+        }
+        // Get the cipher first: if the key was lost, this moves the file aside
+        let cipher = self.fallback_cipher()?;
+        let secrets = read_recovering(&path, |content| decrypt_secrets(&cipher, content))?;
+        Ok(secrets.map(|(secrets, _)| secrets).unwrap_or_default())
+    }
+
+    fn save_fallback(&self, secrets: &HashMap<String, String>) -> Result<()> {
+        let cipher = self.fallback_cipher()?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, serde_json::to_vec(secrets)?.as_slice())
+            .map_err(|_| anyhow!("Failed to encrypt secrets"))?;
+
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        write_atomic(&self.dir.join(FALLBACK_FILE), BASE64.encode(data).as_bytes(), |previous| {
+            std::str::from_utf8(previous).is_ok_and(|content| decrypt_secrets(&cipher, content).is_ok())
+        })
+    }
+}
+
+/// Decrypt the content of the fallback secrets file
+fn decrypt_secrets(cipher: &ChaCha20Poly1305, content: &str) -> Result<HashMap<String, String>> {
+    let data = BASE64.decode(content.trim())?;
+    if data.len() < NONCE_LEN {
+        return Err(anyhow!("Corrupt secrets file"));
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            anyhow!(
+                "{} can't be decrypted with {}; move both files aside to start over",
+                FALLBACK_FILE,
+                FALLBACK_KEY_FILE
+            )
+        })?;
+    Ok(serde_json::from_slice(&plaintext)?)
+}
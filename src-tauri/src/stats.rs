@@ -0,0 +1,191 @@
+use serde::Serialize;
+use crate::script::Script;
+use crate::storage::{HistoryItem, HistoryRange, ModelPrice, TokenUsage};
+
+/// LLM usage of one model
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ModelUsageStats {
+    pub model: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub estimated_cost: Option<f64>, // None if the model has no configured price
+}
+
+/// LLM usage over a range of history
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct LlmUsageStats {
+    pub requests: u64,
+    pub failures: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub unmetered_requests: u64, // Successful requests whose endpoint didn't report usage
+    pub estimated_cost: f64,     // Sum over priced models only
+    pub by_model: Vec<ModelUsageStats>,
+}
+
+fn price_for<'a>(prices: &'a [ModelPrice], model: &str) -> Option<&'a ModelPrice> {
+    prices.iter().find(|p| p.model.trim().eq_ignore_ascii_case(model))
+}
+
+fn cost(price: &ModelPrice, usage: TokenUsage) -> f64 {
+    (usage.prompt_tokens as f64 * price.prompt_per_million + usage.completion_tokens as f64 * price.completion_per_million)
+        / 1_000_000.0
+}
+
+/// Aggregate LLM requests, failures, tokens and estimated cost of the history items in range
+pub fn llm_usage_stats(history: &[HistoryItem], range: &HistoryRange, prices: &[ModelPrice]) -> LlmUsageStats {
+    let mut stats = LlmUsageStats::default();
+
+    for item in history.iter().filter(|item| range.contains(item)) {
+        let Some(model) = item.llm_model.as_deref() else {
+            continue;
+        };
+        let index = match stats.by_model.iter().position(|m| m.model == model) {
+            Some(index) => index,
+            None => {
+                stats.by_model.push(ModelUsageStats { model: model.to_string(), ..Default::default() });
+                stats.by_model.len() - 1
+            }
+        };
+        let entry = &mut stats.by_model[index];
+
+        entry.requests += 1;
+        if item.llm_error.is_some() {
+            entry.failures += 1;
+        } else if item.llm_usage.is_none() {
+            stats.unmetered_requests += 1;
+        }
+        if let Some(usage) = item.llm_usage {
+            entry.prompt_tokens += usage.prompt_tokens;
+            entry.completion_tokens += usage.completion_tokens;
+        }
+    }
+
+    for entry in &mut stats.by_model {
+        entry.estimated_cost = price_for(prices, &entry.model).map(|price| {
+            cost(price, TokenUsage { prompt_tokens: entry.prompt_tokens, completion_tokens: entry.completion_tokens })
+        });
+        stats.requests += entry.requests;
+        stats.failures += entry.failures;
+        stats.prompt_tokens += entry.prompt_tokens;
+        stats.completion_tokens += entry.completion_tokens;
+        stats.estimated_cost += entry.estimated_cost.unwrap_or(0.0);
+    }
+    stats.by_model.sort_by_key(|m| std::cmp::Reverse(m.requests));
+
+    stats
+}
+
+/// Dictation on one day
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct DayStats {
+    pub date: String, // "YYYY-MM-DD"
+    pub items: u64,
+    pub words: u64,
+    pub characters: u64,
+    pub speaking_ms: u64,
+}
+
+/// How many items were recognized in a language
+#[derive(Serialize, Clone, Debug)]
+pub struct LanguageCount {
+    pub language: String, // Empty if the recognizer didn't report it
+    pub items: u64,
+}
+
+/// Dictation volume and speed over a range of history
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct DictationStats {
+    pub items: u64,
+    pub words: u64,
+    pub characters: u64,
+    pub speaking_ms: u64,                    // Only items with a recorded audio length
+    pub average_asr_latency_ms: Option<f64>, // None if no item has a recorded latency
+    pub llm_usage_rate: f64,                 // Share of items sent to an LLM, 0..1
+    pub words_per_minute: Option<f64>,       // Over items with a recorded audio length
+    pub languages: Vec<LanguageCount>,       // Most frequent first
+    pub by_day: Vec<DayStats>,               // Oldest first
+}
+
+/// Count words, taking each CJK character as one word since those scripts don't separate words
+pub fn count_words(text: &str) -> u64 {
+    let mut words = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if Script::of(c).is_cjk() {
+            words += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                words += 1;
+            }
+            in_word = true;
+        } else if c != '\'' && c != '-' {
+            in_word = false;
+        }
+    }
+    words
+}
+
+/// Aggregate words, characters, speaking time, speed and languages of the history items in range
+pub fn dictation_stats(history: &[HistoryItem], range: &HistoryRange) -> DictationStats {
+    let mut stats = DictationStats::default();
+    let mut timed_words = 0;
+    let mut latency_total = 0;
+    let mut latency_items = 0;
+    let mut llm_items = 0;
+
+    for item in history.iter().filter(|item| range.contains(item)) {
+        let words = count_words(&item.text);
+        let characters = item.text.chars().filter(|c| !c.is_whitespace()).count() as u64;
+
+        stats.items += 1;
+        stats.words += words;
+        stats.characters += characters;
+        if item.duration_ms > 0 {
+            stats.speaking_ms += item.duration_ms;
+            timed_words += words;
+        }
+        if item.asr_latency_ms > 0 {
+            latency_total += item.asr_latency_ms;
+            latency_items += 1;
+        }
+        if item.llm_model.is_some() {
+            llm_items += 1;
+        }
+
+        match stats.languages.iter_mut().find(|l| l.language == item.language) {
+            Some(entry) => entry.items += 1,
+            None => stats.languages.push(LanguageCount { language: item.language.clone(), items: 1 }),
+        }
+
+        let date = item.timestamp.get(..10).unwrap_or(&item.timestamp);
+        let day = match stats.by_day.iter_mut().position(|d| d.date == date) {
+            Some(index) => &mut stats.by_day[index],
+            None => {
+                stats.by_day.push(DayStats { date: date.to_string(), ..Default::default() });
+                stats.by_day.last_mut().unwrap()
+            }
+        };
+        day.items += 1;
+        day.words += words;
+        day.characters += characters;
+        day.speaking_ms += item.duration_ms;
+    }
+
+    if stats.items > 0 {
+        stats.llm_usage_rate = llm_items as f64 / stats.items as f64;
+    }
+    if latency_items > 0 {
+        stats.average_asr_latency_ms = Some(latency_total as f64 / latency_items as f64);
+    }
+    if stats.speaking_ms > 0 {
+        stats.words_per_minute = Some(timed_words as f64 / (stats.speaking_ms as f64 / 60_000.0));
+    }
+    stats.languages.sort_by_key(|l| std::cmp::Reverse(l.items));
+    stats.by_day.sort_by(|a, b| a.date.cmp(&b.date));
+
+    stats
+}
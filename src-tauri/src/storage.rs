@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use anyhow::Result;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use anyhow::{anyhow, Context, Result};
+use crate::config_migration::{self, MigrationContext, CONFIG_VERSION};
+use crate::secrets::{self, SecretStore};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
 #[serde(rename_all = "lowercase")]
@@ -29,23 +34,243 @@ pub const DEFAULT_LLM_PROMPT: &str = r#"你是一个语音识别纠错助手。
 请以如下 JSON 格式返回（不要包含其他内容）：
 {"corrected": "纠正后的文本"}"#;
 
+/// A named prompt that can be bound to a trigger
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PromptProfile {
+    pub id: String,
+    pub name: String,
+    pub prompt: String, // Must contain {text} and ask for {"corrected": "..."}
+    #[serde(default = "default_true")]
+    pub apply_guardrail: bool, // Off for profiles that are meant to rewrite or translate
+}
+
+fn builtin_profile(id: &str, name: &str, task: &str, apply_guardrail: bool) -> PromptProfile {
+    PromptProfile {
+        id: id.to_string(),
+        name: name.to_string(),
+        prompt: format!(
+            "你是一个语音输入助手。用户会提供语音识别的原始文本，可能包含同音字、漏字和标点错误。\n\n{}\n\n输入文本：{{text}}\n\n请以如下 JSON 格式返回（不要包含其他内容）：\n{{\"corrected\": \"处理后的文本\"}}",
+            task
+        ),
+        apply_guardrail,
+    }
+}
+
+pub fn default_prompt_profiles() -> Vec<PromptProfile> {
+    vec![
+        PromptProfile {
+            id: "correction".to_string(),
+            name: "纠错".to_string(),
+            prompt: DEFAULT_LLM_PROMPT.to_string(),
+            apply_guardrail: true,
+        },
+        builtin_profile("translate_en", "翻译为英文", "请先修正识别错误，然后将其翻译为自然、地道的英文。只输出译文。", false),
+        builtin_profile("formal_email", "正式邮件", "请先修正识别错误，然后将其改写为一封语气正式、礼貌的邮件正文，保持原文的语言和全部要点。", false),
+        builtin_profile("bullet_summary", "要点摘要", "请先修正识别错误，然后将其总结为简洁的要点列表，每个要点一行，以 \"- \" 开头，保持原文的语言。", false),
+        builtin_profile("code_comment", "代码注释", "请先修正识别错误，然后将其改写为简洁、准确的英文代码注释内容，不要包含注释符号。", false),
+    ]
+}
+
+/// Which prompt, if any, a trigger runs its transcription through
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerLlmMode {
+    #[default]
+    Default,         // The main prompt (custom_prompt or the built-in correction prompt)
+    Off,             // Type the raw transcription
+    Translate,       // Translate into translation.target_language
+    Profile(String), // A prompt profile id
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TranslationConfig {
+    pub target_language: String, // Language code like "en", or a language name
+    pub correct_same_language: bool, // Correct instead of translating when the speech is already in the target language
+}
+
+impl Default for TranslationConfig {
+    fn default() -> Self {
+        Self {
+            target_language: "en".to_string(),
+            correct_same_language: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TriggerLlmModes {
+    #[serde(default)]
+    pub mouse: TriggerLlmMode,
+    #[serde(default)]
+    pub hold: TriggerLlmMode,
+    #[serde(default)]
+    pub toggle: TriggerLlmMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub enum LlmProvider {
+    #[default]
+    #[serde(rename = "openai")]
+    OpenAi, // Also any OpenAI-compatible endpoint
+    #[serde(rename = "anthropic")]
+    Anthropic,
+    #[serde(rename = "azure_openai")]
+    AzureOpenAi,
+    #[serde(rename = "ollama")]
+    Ollama,
+    #[serde(rename = "gemini")]
+    Gemini,
+}
+
+/// An additional LLM endpoint tried when the ones before it fail
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LlmEndpoint {
+    #[serde(default)]
+    pub provider: LlmProvider,
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+    #[serde(default)]
+    pub api_version: String,
+    #[serde(default = "default_llm_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_true")]
+    pub structured_output: bool, // Use JSON schema output when the provider supports it
+}
+
+fn default_llm_timeout_secs() -> u64 {
+    30
+}
+
+fn default_llm_max_retries() -> u32 {
+    2
+}
+
+fn default_circuit_breaker_cooldown_secs() -> u64 {
+    60
+}
+
+fn default_circuit_breaker_threshold() -> u32 {
+    3
+}
+
+/// Limits on how far an LLM correction may drift from the recognized text
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GuardrailConfig {
+    pub enabled: bool,
+    pub max_edit_ratio: f64, // Character edits / length, ignoring punctuation and spacing
+    pub min_length_ratio: f64,
+    pub max_length_ratio: f64,
+    pub reject_script_change: bool, // e.g. Chinese dictation answered in English
+}
+
+impl Default for GuardrailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_edit_ratio: 0.5,
+            min_length_ratio: 0.5,
+            max_length_ratio: 1.6,
+            reject_script_change: true,
+        }
+    }
+}
+
+/// Price of a model in any currency, per million tokens
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ModelPrice {
+    pub model: String, // Matched case-insensitively against the model that answered
+    pub prompt_per_million: f64,
+    pub completion_per_million: f64,
+}
+
+/// Earlier dictation and glossary terms sent along with the text, to resolve names and terms
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LlmContextConfig {
+    pub include_history: bool,
+    pub history_items: usize,      // At most this many recent history items
+    pub history_window_secs: u64,  // Only items dictated this recently
+    pub glossary: Vec<String>,     // Names, jargon and hotwords to spell exactly
+    pub max_context_tokens: usize, // Estimated budget for glossary and history together
+}
+
+impl Default for LlmContextConfig {
+    fn default() -> Self {
+        Self {
+            include_history: false,
+            history_items: 3,
+            history_window_secs: 300,
+            glossary: Vec::new(),
+            max_context_tokens: 400,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct LlmConfig {
     pub enabled: bool,
+    #[serde(default)]
+    pub provider: LlmProvider,
     pub base_url: String,
     pub api_key: String,
     pub model: String,
     pub custom_prompt: String, // Empty means use default
+    #[serde(default)]
+    pub stream: bool, // Use SSE streaming (`stream: true`)
+    #[serde(default)]
+    pub progressive_typing: bool, // Type the correction as it streams in (requires stream)
+    #[serde(default)]
+    pub api_version: String, // Azure OpenAI only; empty means the default version
+    #[serde(default = "default_llm_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_llm_max_retries")]
+    pub max_retries: u32, // Per endpoint, on network errors, 429 and 5xx
+    #[serde(default = "default_circuit_breaker_cooldown_secs")]
+    pub circuit_breaker_cooldown_secs: u64, // How long a failed endpoint is skipped
+    #[serde(default = "default_circuit_breaker_threshold")]
+    pub circuit_breaker_threshold: u32, // Consecutive network errors, 429 or 5xx before an endpoint is skipped
+    #[serde(default)]
+    pub fallback_endpoints: Vec<LlmEndpoint>, // Tried in order after the primary endpoint
+    #[serde(default)]
+    pub guardrail: GuardrailConfig,
+    #[serde(default = "default_true")]
+    pub structured_output: bool, // Use JSON schema output when the provider and endpoint support it
+    #[serde(default = "default_prompt_profiles")]
+    pub prompt_profiles: Vec<PromptProfile>,
+    #[serde(default)]
+    pub trigger_modes: TriggerLlmModes,
+    #[serde(default)]
+    pub translation: TranslationConfig,
+    #[serde(default)]
+    pub context: LlmContextConfig,
+    #[serde(default)]
+    pub model_prices: Vec<ModelPrice>,
 }
 
 impl Default for LlmConfig {
     fn default() -> Self {
         Self {
             enabled: false,
+            provider: LlmProvider::default(),
             base_url: "https://api.openai.com/v1".to_string(),
             api_key: "".to_string(),
             model: "gpt-4o-mini".to_string(),
             custom_prompt: "".to_string(),
+            stream: false,
+            progressive_typing: false,
+            api_version: "".to_string(),
+            timeout_secs: default_llm_timeout_secs(),
+            max_retries: default_llm_max_retries(),
+            circuit_breaker_cooldown_secs: default_circuit_breaker_cooldown_secs(),
+            circuit_breaker_threshold: default_circuit_breaker_threshold(),
+            fallback_endpoints: Vec::new(),
+            guardrail: GuardrailConfig::default(),
+            structured_output: true,
+            prompt_profiles: default_prompt_profiles(),
+            trigger_modes: TriggerLlmModes::default(),
+            translation: TranslationConfig::default(),
+            context: LlmContextConfig::default(),
+            model_prices: Vec::new(),
         }
     }
 }
@@ -56,8 +281,103 @@ pub struct ProxyConfig {
     pub url: String, // e.g., "http://127.0.0.1:7890" or "socks5://127.0.0.1:1080"
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ModelMirror {
+    pub name: String,         // e.g., "Hugging Face", "ModelScope", "Office NAS"
+    pub url_template: String, // "{name}" becomes the build name, "{file}" the archive file name
+    #[serde(default)]
+    pub urls: HashMap<String, String>, // Full archive URLs by build name, used instead of the template
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ModelUpdateConfig {
+    pub enabled: bool,
+    pub interval_hours: u64,
+    pub catalog_url: String, // Empty means the sherpa-onnx GitHub release
+    #[serde(default)]
+    pub dismissed_builds: Vec<String>, // Builds the user chose not to be notified about again
+}
+
+impl Default for ModelUpdateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: 24,
+            catalog_url: "".to_string(),
+            dismissed_builds: Vec::new(),
+        }
+    }
+}
+
+/// How much dictation history is kept
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryConfig {
+    pub max_items: usize,   // 0 means unlimited
+    pub max_age_days: u32,  // 0 means unlimited
+    pub keep_pinned: bool,  // Pinned items are exempt from both limits and don't count toward max_items
+    pub incognito: bool,    // Don't save dictations to history at all
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_items: 0,
+            max_age_days: 0,
+            keep_pinned: true,
+            incognito: false,
+        }
+    }
+}
+
+impl HistoryConfig {
+    /// Remove the items the retention limits don't allow from a newest-first history;
+    /// returns how many were removed
+    pub fn prune(&self, history: &mut Vec<HistoryItem>) -> usize {
+        let cutoff = (self.max_age_days > 0).then(|| {
+            (chrono::Local::now() - chrono::Duration::days(self.max_age_days as i64))
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        });
+        let count = history.len();
+        let mut kept = 0;
+        history.retain(|item| {
+            if self.keep_pinned && item.pinned {
+                return true;
+            }
+            if cutoff.as_deref().is_some_and(|cutoff| item.timestamp.as_str() < cutoff) {
+                return false;
+            }
+            kept += 1;
+            self.max_items == 0 || kept <= self.max_items
+        });
+        count - history.len()
+    }
+}
+
+/// A named set of settings to switch between, e.g. "office" or "home with proxy"
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ConfigProfile {
+    pub name: String,
+    pub input_device: String,
+    pub model_version: ModelVersion,
+    pub language: String,
+    pub trigger_mouse: bool,
+    pub trigger_hold: bool,
+    pub trigger_toggle: bool,
+    pub llm_config: LlmConfig,
+    pub proxy: ProxyConfig,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AppConfig {
+    #[serde(default)]
+    pub config_version: u32, // Missing in configs from before versioning, see config_migration
     pub trigger_mouse: bool,
     pub trigger_hold: bool,
     pub trigger_toggle: bool,
@@ -71,11 +391,22 @@ pub struct AppConfig {
     pub llm_config: LlmConfig,
     #[serde(default)]
     pub proxy: ProxyConfig,
+    #[serde(default)]
+    pub model_mirrors: Vec<ModelMirror>, // Tried alongside GitHub, fastest first
+    #[serde(default)]
+    pub model_update: ModelUpdateConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub profiles: Vec<ConfigProfile>,
+    #[serde(default)]
+    pub active_profile: Option<String>, // Name of the profile last switched to
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
+            config_version: CONFIG_VERSION,
             trigger_mouse: true,
             trigger_hold: true,
             trigger_toggle: true,
@@ -85,6 +416,86 @@ impl Default for AppConfig {
             input_device: "".to_string(), // Default device
             llm_config: LlmConfig::default(),
             proxy: ProxyConfig::default(),
+            model_mirrors: Vec::new(),
+            model_update: ModelUpdateConfig::default(),
+            history: HistoryConfig::default(),
+            profiles: Vec::new(),
+            active_profile: None,
+        }
+    }
+}
+
+impl AppConfig {
+    /// The LLM config in use and those saved in profiles
+    pub fn llm_configs(&self) -> impl Iterator<Item = &LlmConfig> {
+        std::iter::once(&self.llm_config).chain(self.profiles.iter().map(|p| &p.llm_config))
+    }
+
+    pub fn llm_configs_mut(&mut self) -> impl Iterator<Item = &mut LlmConfig> {
+        std::iter::once(&mut self.llm_config).chain(self.profiles.iter_mut().map(|p| &mut p.llm_config))
+    }
+
+    /// Save the current settings as a profile, replacing any profile with the same name
+    pub fn save_profile(&mut self, name: &str) {
+        let profile = ConfigProfile {
+            name: name.to_string(),
+            input_device: self.input_device.clone(),
+            model_version: self.model_version.clone(),
+            language: self.language.clone(),
+            trigger_mouse: self.trigger_mouse,
+            trigger_hold: self.trigger_hold,
+            trigger_toggle: self.trigger_toggle,
+            llm_config: self.llm_config.clone(),
+            proxy: self.proxy.clone(),
+        };
+        match self.profiles.iter_mut().find(|p| p.name == name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+        self.active_profile = Some(name.to_string());
+    }
+
+    /// Copy a profile's settings into the config; returns false if there is no such profile
+    pub fn apply_profile(&mut self, name: &str) -> bool {
+        let Some(profile) = self.profiles.iter().find(|p| p.name == name).cloned() else {
+            return false;
+        };
+        self.input_device = profile.input_device;
+        self.model_version = profile.model_version;
+        self.language = profile.language;
+        self.trigger_mouse = profile.trigger_mouse;
+        self.trigger_hold = profile.trigger_hold;
+        self.trigger_toggle = profile.trigger_toggle;
+        self.llm_config = profile.llm_config;
+        self.proxy = profile.proxy;
+        self.active_profile = Some(profile.name);
+        true
+    }
+
+    /// The profile after the active one, wrapping around; None if there are no profiles
+    pub fn next_profile(&self) -> Option<&str> {
+        let current = self
+            .active_profile
+            .as_deref()
+            .and_then(|name| self.profiles.iter().position(|p| p.name == name));
+        let next = current.map_or(0, |index| (index + 1) % self.profiles.len());
+        self.profiles.get(next).map(|p| p.name.as_str())
+    }
+}
+
+/// Tokens billed for one LLM request
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Combine partial reports (e.g. prompt and completion counts sent in separate stream events)
+    pub fn merge(self, other: TokenUsage) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self.prompt_tokens.max(other.prompt_tokens),
+            completion_tokens: self.completion_tokens.max(other.completion_tokens),
         }
     }
 }
@@ -94,60 +505,489 @@ pub struct HistoryItem {
     pub id: String,
     pub timestamp: String,
     pub text: String,
-    pub duration_ms: u64,
+    pub duration_ms: u64, // Length of the recording; 0 for items from before it was recorded
+    #[serde(default)]
+    pub asr_latency_ms: u64, // Time the recognizer took; 0 if unknown
+    #[serde(default)]
+    pub llm_rejection: Option<String>, // Why the LLM correction was discarded, if it was
+    #[serde(default)]
+    pub language: String, // Language detected by the recognizer
+    #[serde(default)]
+    pub original_text: Option<String>, // Recognized text, when `text` is a translation of it
+    #[serde(default)]
+    pub translated_to: Option<String>,
+    #[serde(default)]
+    pub llm_model: Option<String>, // Model that answered, or the primary model if all endpoints failed
+    #[serde(default)]
+    pub llm_usage: Option<TokenUsage>, // None if the endpoint didn't report usage
+    #[serde(default)]
+    pub llm_error: Option<String>, // Why the LLM request failed, if it did
+    #[serde(default)]
+    pub edited_from: Option<String>, // Text before the user first edited it
+    #[serde(default)]
+    pub pinned: bool, // Pinned items survive clearing the history
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Inclusive date range ("YYYY-MM-DD") to select history items; open ends are unbounded
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct HistoryRange {
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+impl HistoryRange {
+    pub fn contains(&self, item: &HistoryItem) -> bool {
+        // Timestamps are "YYYY-MM-DD HH:MM:SS", so the date prefix compares lexicographically
+        let date = item.timestamp.get(..10).unwrap_or(&item.timestamp);
+        self.from.as_deref().is_none_or(|from| date >= from) && self.to.as_deref().is_none_or(|to| date <= to)
+    }
+}
+
+/// Rolling backup kept next to a file written by `write_atomic`
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Write a file so that a crash leaves either the old or the new content, never a mix:
+/// write a temp file, fsync it and rename it over the original. The previous content is
+/// kept as the `.bak` backup if `is_valid` accepts it (a corrupt file must not replace a good backup).
+pub fn write_atomic(path: &Path, content: &[u8], is_valid: impl Fn(&[u8]) -> bool) -> Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+    }
+
+    if let Ok(previous) = fs::read(path) {
+        if is_valid(&previous) {
+            fs::copy(path, backup_path(path))?;
+        }
+    }
+    fs::rename(&tmp_path, path)?;
+
+    // Persist the rename itself
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        fs::File::open(dir).and_then(|d| d.sync_all()).ok();
+    }
+    Ok(())
+}
+
+/// Backup check for the JSON files passed to `write_atomic`
+pub fn is_json(content: &[u8]) -> bool {
+    serde_json::from_slice::<serde::de::IgnoredAny>(content).is_ok()
+}
+
+/// Read a file written by `write_atomic`, falling back to its backup when the file is missing
+/// or doesn't parse. Returns the parsed value with the content it came from; Ok(None) if there
+/// is neither, or the file's parse error if neither parses.
+pub fn read_recovering<T>(path: &Path, parse: impl Fn(&str) -> Result<T>) -> Result<Option<(T, String)>> {
+    let error = match fs::read_to_string(path) {
+        Ok(content) => match parse(&content) {
+            Ok(value) => return Ok(Some((value, content))),
+            Err(e) => Some(e),
+        },
+        Err(_) => None,
+    };
+
+    if let Ok(content) = fs::read_to_string(backup_path(path)) {
+        if let Ok(value) = parse(&content) {
+            let problem = error.as_ref().map_or("is missing".to_string(), |e| format!("is unreadable ({:#})", e));
+            eprintln!("[STORAGE] {} {}, recovered from backup", path.display(), problem);
+            return Ok(Some((value, content)));
+        }
+    }
+
+    error.map_or(Ok(None), Err)
+}
+
+/// Merge `patch` into `target`: objects are merged key by key, any other value replaces the target
+fn merge_json(target: &mut serde_json::Value, patch: serde_json::Value) {
+    match (target, patch) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge_json(target.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+/// Move an unreadable file aside so that saving over it doesn't lose it
+fn set_aside_invalid(path: &Path) -> std::io::Result<PathBuf> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let invalid_path = path.with_file_name(format!("{}.invalid-{}.json", stem, chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    fs::rename(path, &invalid_path)?;
+    Ok(invalid_path)
+}
+
+/// What changed in storage, passed to change listeners
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageChange {
+    Config,
+    History,
 }
 
+type ChangeListener = Box<dyn Fn(StorageChange) + Send + Sync>;
+
+/// Config and history, cached in memory and written through to disk. Every change runs under
+/// the cache lock, so concurrent read-modify-write updates can't overwrite each other.
 pub struct StorageService {
     config_path: PathBuf,
     history_path: PathBuf,
+    local_data_dir: PathBuf, // Where models live, needed by config migrations
+    secrets: SecretStore,
+    config_error: Mutex<Option<String>>, // Why config.json couldn't be loaded, if it couldn't
+    config: Mutex<Option<AppConfig>>, // Read from disk on first use
+    history: Mutex<Option<Vec<HistoryItem>>>,
+    listeners: Mutex<Vec<ChangeListener>>,
 }
 
 impl StorageService {
-    pub fn new(app_dir: PathBuf) -> Self {
+    pub fn new(app_dir: PathBuf, local_data_dir: PathBuf) -> Self {
         if !app_dir.exists() {
             fs::create_dir_all(&app_dir).ok();
         }
         Self {
             config_path: app_dir.join("config.json"),
             history_path: app_dir.join("history.json"),
+            local_data_dir,
+            secrets: SecretStore::new(app_dir),
+            config_error: Mutex::new(None),
+            config: Mutex::new(None),
+            history: Mutex::new(None),
+            listeners: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Call `listener` after every successful config or history change.
+    /// Listeners run on the thread that made the change and must not modify storage.
+    pub fn subscribe(&self, listener: impl Fn(StorageChange) + Send + Sync + 'static) {
+        self.listeners.lock().unwrap().push(Box::new(listener));
+    }
+
+    fn notify(&self, change: StorageChange) {
+        for listener in self.listeners.lock().unwrap().iter() {
+            listener(change);
+        }
+    }
+
+    fn migration_context(&self) -> MigrationContext<'_> {
+        MigrationContext { local_data_dir: &self.local_data_dir }
+    }
+
+    /// Defaults for a new installation, with the same adjustments migrations make to old configs
+    fn default_config(&self) -> AppConfig {
+        let mut value = serde_json::to_value(AppConfig::default()).unwrap_or_default();
+        value["config_version"] = 0.into();
+        config_migration::migrate(&mut value, &self.migration_context());
+        serde_json::from_value(value).unwrap_or_default()
+    }
+
+    /// Parse and migrate config.json content; returns the config and the version it was written with
+    fn parse_config(&self, content: &str) -> Result<(AppConfig, u32)> {
+        let mut value: serde_json::Value = serde_json::from_str(content).context("config.json is not valid JSON")?;
+        let version = config_migration::config_version(&value);
+        if version > CONFIG_VERSION {
+            eprintln!("[CONFIG] config.json is from a newer version ({} > {}); unknown settings will be lost on save", version, CONFIG_VERSION);
+        }
+        config_migration::migrate(&mut value, &self.migration_context());
+        let config = serde_json::from_value(value).context("config.json doesn't match the config schema")?;
+        Ok((config, version))
+    }
+
+    /// Keep an unreadable config.json aside, so saving defaults over it doesn't lose it, and remember why
+    fn report_invalid_config(&self, error: &anyhow::Error) {
+        let mut config_error = self.config_error.lock().unwrap();
+        if config_error.is_some() {
+            return;
+        }
+        let message = match set_aside_invalid(&self.config_path) {
+            Ok(invalid_path) => format!("{:#}. Using default settings; the old file was kept as {}", error, invalid_path.display()),
+            Err(e) => format!("{:#}. Using default settings; moving the old file aside failed: {}", error, e),
+        };
+        eprintln!("[CONFIG] {}", message);
+        *config_error = Some(message);
+    }
+
+    /// Why config.json couldn't be loaded, if it couldn't
+    pub fn config_error(&self) -> Option<String> {
+        self.config_error.lock().unwrap().clone()
+    }
+
+    /// Load the config at startup: migrate it to the current version (keeping a backup of
+    /// the old file) or create it for a new installation
+    pub fn init_config(&self) -> AppConfig {
+        let mut cached = self.config.lock().unwrap();
+        let config = match read_recovering(&self.config_path, |content| self.parse_config(content)) {
+            Ok(Some(((config, version), content))) => {
+                if version < CONFIG_VERSION {
+                    let backup_path = self.config_path.with_file_name(format!("config.v{}.bak.json", version));
+                    if let Err(e) = fs::write(&backup_path, content) {
+                        eprintln!("[CONFIG] Failed to back up config before migrating, not saving it: {}", e);
+                        config
+                    } else {
+                        match self.write_config(&config, config.clone()) {
+                            Ok(saved) => {
+                                println!("[CONFIG] Migrated config to version {}, backup at {}", CONFIG_VERSION, backup_path.display());
+                                saved
+                            }
+                            Err(e) => {
+                                eprintln!("[CONFIG] Failed to save migrated config: {}", e);
+                                config
+                            }
+                        }
+                    }
+                } else {
+                    config
+                }
+            }
+            Ok(None) => {
+                let config = self.default_config();
+                match self.write_config(&config, config.clone()) {
+                    Ok(saved) => saved,
+                    Err(e) => {
+                        eprintln!("[CONFIG] Failed to create config.json: {}", e);
+                        config
+                    }
+                }
+            }
+            Err(e) => {
+                self.report_invalid_config(&e);
+                self.default_config()
+            }
+        };
+        *cached = Some(config.clone());
+        config
+    }
+
+    fn read_config(&self) -> AppConfig {
+        match read_recovering(&self.config_path, |content| self.parse_config(content)) {
+            Ok(Some(((config, _), _))) => config,
+            Ok(None) => self.default_config(),
+            Err(e) => {
+                self.report_invalid_config(&e);
+                self.default_config()
+            }
         }
     }
 
     pub fn load_config(&self) -> AppConfig {
-        if let Ok(content) = fs::read_to_string(&self.config_path) {
-            serde_json::from_str(&content).unwrap_or_default()
-        } else {
-            AppConfig::default()
+        self.config.lock().unwrap().get_or_insert_with(|| self.read_config()).clone()
+    }
+
+    /// Write a config to disk and return it as written; API keys go to the secret store and
+    /// only references to them are written
+    fn write_config(&self, previous: &AppConfig, mut config: AppConfig) -> Result<AppConfig> {
+        for llm_config in config.llm_configs_mut() {
+            self.secrets.store_api_keys(llm_config)?;
+        }
+
+        let content = serde_json::to_string_pretty(&config)?;
+        write_atomic(&self.config_path, content.as_bytes(), is_json)?;
+
+        // Drop stored keys the config (including its profiles) no longer refers to
+        let kept: Vec<String> = config.llm_configs().flat_map(secrets::referenced_ids).collect();
+        for id in previous.llm_configs().flat_map(secrets::referenced_ids) {
+            if !kept.contains(&id) {
+                self.secrets.delete(&id);
+            }
         }
+        Ok(config)
+    }
+
+    /// Modify the current config and save it, all under the lock; returns the saved config
+    pub fn update_config(&self, update: impl FnOnce(&mut AppConfig)) -> Result<AppConfig> {
+        self.try_update_config(|config| {
+            update(config);
+            Ok(())
+        })
+    }
+
+    /// Like `update_config`, but nothing is saved if `update` fails
+    fn try_update_config(&self, update: impl FnOnce(&mut AppConfig) -> Result<()>) -> Result<AppConfig> {
+        let saved = {
+            let mut cached = self.config.lock().unwrap();
+            let current = cached.get_or_insert_with(|| self.read_config());
+            let mut config = current.clone();
+            update(&mut config)?;
+            let saved = self.write_config(current, config)?;
+            *current = saved.clone();
+            saved
+        };
+        self.notify(StorageChange::Config);
+        Ok(saved)
     }
 
     pub fn save_config(&self, config: &AppConfig) -> Result<()> {
-        let content = serde_json::to_string_pretty(config)?;
-        fs::write(&self.config_path, content)?;
-        Ok(())
+        self.update_config(|current| *current = config.clone()).map(|_| ())
     }
 
-    pub fn load_history(&self) -> Vec<HistoryItem> {
-        if let Ok(content) = fs::read_to_string(&self.history_path) {
-            serde_json::from_str(&content).unwrap_or_default()
+    /// Change only the settings present in `patch` (a partial config: nested objects are merged
+    /// field by field, anything else is replaced), so concurrent changes to other settings survive
+    pub fn patch_config(&self, patch: serde_json::Value) -> Result<AppConfig> {
+        self.try_update_config(|config| {
+            let mut value = serde_json::to_value(&*config)?;
+            merge_json(&mut value, patch);
+            *config = serde_json::from_value(value).context("Invalid settings")?;
+            Ok(())
+        })
+    }
+
+    /// Write the config to `path` for use on another machine. API keys are left out unless
+    /// `include_secrets` is set, in which case they are written in plaintext.
+    pub fn export_config(&self, path: &Path, include_secrets: bool) -> Result<()> {
+        let mut config = self.load_config();
+        for llm_config in config.llm_configs_mut() {
+            *llm_config = if include_secrets {
+                self.secrets.resolve_api_keys(llm_config)
+            } else {
+                secrets::without_api_keys(llm_config)
+            };
+        }
+        let content = serde_json::to_string_pretty(&config)?;
+        if include_secrets {
+            // Plaintext keys must not be readable by other users
+            secrets::write_private(path, content.as_bytes())
         } else {
-            Vec::new()
+            write_atomic(path, content.as_bytes(), is_json)
+        }
+    }
+
+    /// Replace the config with an exported one; returns the saved config. The model directory
+    /// stays local, and API keys missing from the file are kept from the matching local endpoint.
+    pub fn import_config(&self, path: &Path) -> Result<AppConfig> {
+        let content = fs::read_to_string(path)?;
+        let (mut imported, _) = self.parse_config(&content).context("Not a valid settings file")?;
+        self.update_config(|config| {
+            imported.model_dir = config.model_dir.clone();
+            for llm_config in imported.llm_configs_mut() {
+                self.secrets.import_api_keys(llm_config, config);
+            }
+            *config = imported;
+        })
+    }
+
+    /// A copy of the LLM config with the stored API keys filled in, for making requests
+    pub fn resolve_llm_config(&self, config: &LlmConfig) -> LlmConfig {
+        self.secrets.resolve_api_keys(config)
+    }
+
+    fn read_history(&self) -> Vec<HistoryItem> {
+        match read_recovering(&self.history_path, |content| Ok(serde_json::from_str(content)?)) {
+            Ok(Some((history, _))) => history,
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                match set_aside_invalid(&self.history_path) {
+                    Ok(invalid_path) => eprintln!("[HISTORY] {:#}; starting a new history, the old file was kept as {}", e, invalid_path.display()),
+                    Err(move_error) => eprintln!("[HISTORY] {:#}; moving the file aside failed: {}", e, move_error),
+                }
+                Vec::new()
+            }
         }
     }
 
-    pub fn save_history(&self, history: &Vec<HistoryItem>) -> Result<()> {
-        let content = serde_json::to_string_pretty(history)?;
-        fs::write(&self.history_path, content)?;
-        Ok(())
+    pub fn load_history(&self) -> Vec<HistoryItem> {
+        self.history.lock().unwrap().get_or_insert_with(|| self.read_history()).clone()
+    }
+
+    /// Modify the history and save it, all under the lock; returns what `update` returns.
+    /// The cache is only replaced once the write succeeded.
+    pub fn update_history<T>(&self, update: impl FnOnce(&mut Vec<HistoryItem>) -> T) -> Result<T> {
+        let result = {
+            let mut cached = self.history.lock().unwrap();
+            let current = cached.get_or_insert_with(|| self.read_history());
+            let mut history = current.clone();
+            let result = update(&mut history);
+            let content = serde_json::to_string_pretty(&history)?;
+            write_atomic(&self.history_path, content.as_bytes(), is_json)?;
+            *current = history;
+            result
+        };
+        self.notify(StorageChange::History);
+        Ok(result)
+    }
+
+    pub fn save_history(&self, history: &[HistoryItem]) -> Result<()> {
+        self.update_history(|current| *current = history.to_vec())
     }
 
+    /// Add a new item and apply the retention settings
     pub fn add_history_item(&self, item: HistoryItem) -> Result<()> {
-        let mut history = self.load_history();
-        history.insert(0, item); // Newest first
-        self.save_history(&history)
+        let retention = self.load_config().history;
+        self.update_history(|history| {
+            history.insert(0, item); // Newest first
+            retention.prune(history);
+        })
     }
-    
+
+    /// Apply the retention settings; returns how many items were removed.
+    /// The history is only rewritten if something has to go.
+    pub fn prune_history(&self) -> Result<usize> {
+        let retention = self.load_config().history;
+        if retention.prune(&mut self.load_history()) == 0 {
+            return Ok(0);
+        }
+        self.update_history(|history| retention.prune(history))
+    }
+
+    /// Modify the history item with the given id and save the history
+    fn update_history_item(&self, id: &str, update: impl FnOnce(&mut HistoryItem)) -> Result<()> {
+        self.update_history(|history| {
+            history.iter_mut().find(|item| item.id == id).map(update).is_some()
+        })?
+        .then_some(())
+        .ok_or_else(|| anyhow!("History item {} not found", id))
+    }
+
+    pub fn delete_history_item(&self, id: &str) -> Result<()> {
+        self.update_history(|history| {
+            let count = history.len();
+            history.retain(|item| item.id != id);
+            history.len() < count
+        })?
+        .then_some(())
+        .ok_or_else(|| anyhow!("History item {} not found", id))
+    }
+
+    /// Replace an item's text, keeping the text from before the first edit
+    pub fn edit_history_item(&self, id: &str, text: String) -> Result<()> {
+        self.update_history_item(id, |item| {
+            if item.text != text {
+                let previous = std::mem::replace(&mut item.text, text);
+                item.edited_from.get_or_insert(previous);
+            }
+        })
+    }
+
+    pub fn set_history_item_pinned(&self, id: &str, pinned: bool) -> Result<()> {
+        self.update_history_item(id, |item| item.pinned = pinned)
+    }
+
+    /// Replace an item's tags; blank and repeated tags are dropped
+    pub fn set_history_item_tags(&self, id: &str, tags: Vec<String>) -> Result<()> {
+        let mut normalized: Vec<String> = Vec::new();
+        for tag in tags {
+            let tag = tag.trim();
+            if !tag.is_empty() && !normalized.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                normalized.push(tag.to_string());
+            }
+        }
+        self.update_history_item(id, |item| item.tags = normalized)
+    }
+
+    /// Remove all history items except pinned ones
     pub fn clear_history(&self) -> Result<()> {
-        self.save_history(&Vec::new())
+        self.update_history(|history| history.retain(|item| item.pinned))
     }
 }
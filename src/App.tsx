@@ -23,6 +23,11 @@ function App() {
       setIsModelLoading(status.downloaded && !status.loaded);
     });
 
+    // Settings could not be read at startup and defaults are in use
+    api.getConfigError().then(error => {
+      if (error) alert(`Failed to load settings: ${error}`);
+    });
+
     // Check if initial setup is needed (no device or no model downloaded)
     Promise.all([api.getConfig(), api.getModelVersionsStatus()]).then(([config, versionsStatus]) => {
       const noDevice = !config.input_device || config.input_device === "";
@@ -1,15 +1,30 @@
 import { useEffect, useState, useRef } from "react";
-import { Copy, Trash2, Clock } from "lucide-react";
-import { api, events, HistoryItem } from "../lib/api";
+import { Copy, Trash2, Clock, Download, Upload, Pin, Pencil, Tag, X } from "lucide-react";
+import { open, save } from "@tauri-apps/plugin-dialog";
+import { api, events, ExportFormat, HistoryItem } from "../lib/api";
+
+const EXPORT_FORMATS: { value: ExportFormat; label: string; extension: string }[] = [
+    { value: "markdown", label: "Markdown", extension: "md" },
+    { value: "csv", label: "CSV", extension: "csv" },
+    { value: "json", label: "JSON", extension: "json" },
+    { value: "jsonl", label: "JSON Lines", extension: "jsonl" },
+    { value: "text", label: "Plain text", extension: "txt" },
+];
 
 export function HistoryList() {
     const [items, setItems] = useState<HistoryItem[]>([]);
     const scrollRef = useRef<HTMLDivElement>(null);
+    const [showExport, setShowExport] = useState(false);
+    const [exportFormat, setExportFormat] = useState<ExportFormat>("markdown");
+    const [exportFrom, setExportFrom] = useState("");
+    const [exportTo, setExportTo] = useState("");
+    const [exportSearch, setExportSearch] = useState("");
 
     useEffect(() => {
         api.getHistory().then(setItems);
-        const u = events.onTranscriptionUpdate((item) => {
-            setItems(prev => [item, ...prev]);
+        // Reload on every change, including ones made elsewhere (new dictation, clearing, imports)
+        const u = events.onHistoryChanged(() => {
+            api.getHistory().then(setItems);
         });
         return () => { u.then(f => f()); };
     }, []);
@@ -19,23 +34,90 @@ export function HistoryList() {
     };
 
     const clearAll = async () => {
-        if (confirm("Clear all history?")) {
+        if (confirm("Clear all history? Pinned items are kept.")) {
             await api.clearHistory();
-            setItems([]);
         }
     }
 
+    const editText = async (item: HistoryItem) => {
+        const text = prompt("Edit text", item.text);
+        if (text === null || text === item.text) return;
+        await api.editHistoryItem(item.id, text).catch(e => alert(`Edit failed: ${e}`));
+    };
+
+    const editTags = async (item: HistoryItem) => {
+        const tags = prompt("Tags (comma separated)", item.tags.join(", "));
+        if (tags === null) return;
+        await api.setHistoryItemTags(item.id, tags.split(",")).catch(e => alert(`Saving tags failed: ${e}`));
+    };
+
+    const exportHistory = async () => {
+        const format = EXPORT_FORMATS.find(f => f.value === exportFormat)!;
+        const path = await save({
+            defaultPath: `fastsp-history.${format.extension}`,
+            filters: [{ name: format.label, extensions: [format.extension] }],
+        });
+        if (!path) return;
+        try {
+            const range = { from: exportFrom || undefined, to: exportTo || undefined };
+            const count = await api.exportHistory(exportFormat, path, range, exportSearch || undefined);
+            alert(`Exported ${count} item${count === 1 ? "" : "s"}`);
+            setShowExport(false);
+        } catch (e) {
+            alert(`Export failed: ${e}`);
+        }
+    };
+
+    const importHistory = async () => {
+        const path = await open({
+            multiple: false,
+            filters: [{ name: "History", extensions: ["json", "jsonl"] }],
+        });
+        if (!path || typeof path !== "string") return;
+        try {
+            const { added, skipped, pruned } = await api.importHistory(path);
+            const retention = pruned > 0 ? `, ${pruned} removed by history retention settings` : "";
+            alert(`Imported ${added} item${added === 1 ? "" : "s"}, skipped ${skipped} already in history${retention}`);
+        } catch (e) {
+            alert(`Import failed: ${e}`);
+        }
+    };
+
     return (
         <div className="flex flex-col h-full bg-white/80 backdrop-blur-md rounded-2xl border border-slate-200 shadow-sm overflow-hidden">
             <div className="p-4 border-b border-slate-100 flex justify-between items-center bg-slate-50/50">
                 <h2 className="text-sm font-semibold text-slate-500 uppercase tracking-wider">Recent Transcriptions</h2>
-                {items.length > 0 && (
-                    <button onClick={clearAll} className="p-2 text-slate-400 hover:text-red-500 hover:bg-red-50 rounded-lg transition-colors">
-                        <Trash2 className="w-4 h-4" />
+                <div className="flex items-center gap-1">
+                    <button onClick={importHistory} title="Import" className="p-2 text-slate-400 hover:text-chinese-indigo hover:bg-slate-100 rounded-lg transition-colors">
+                        <Upload className="w-4 h-4" />
                     </button>
-                )}
+                    {items.length > 0 && (
+                        <>
+                            <button onClick={() => setShowExport(!showExport)} title="Export" className="p-2 text-slate-400 hover:text-chinese-indigo hover:bg-slate-100 rounded-lg transition-colors">
+                                <Download className="w-4 h-4" />
+                            </button>
+                            <button onClick={clearAll} className="p-2 text-slate-400 hover:text-red-500 hover:bg-red-50 rounded-lg transition-colors">
+                                <Trash2 className="w-4 h-4" />
+                            </button>
+                        </>
+                    )}
+                </div>
             </div>
 
+            {showExport && (
+                <div className="p-3 border-b border-slate-100 bg-slate-50/50 flex flex-wrap items-center gap-2 text-xs">
+                    <select value={exportFormat} onChange={e => setExportFormat(e.target.value as ExportFormat)} className="px-2 py-1 border border-slate-200 rounded-md bg-white">
+                        {EXPORT_FORMATS.map(f => <option key={f.value} value={f.value}>{f.label}</option>)}
+                    </select>
+                    <input type="date" value={exportFrom} onChange={e => setExportFrom(e.target.value)} title="From" className="px-2 py-1 border border-slate-200 rounded-md bg-white" />
+                    <input type="date" value={exportTo} onChange={e => setExportTo(e.target.value)} title="To" className="px-2 py-1 border border-slate-200 rounded-md bg-white" />
+                    <input type="text" value={exportSearch} onChange={e => setExportSearch(e.target.value)} placeholder="Containing..." className="flex-1 min-w-24 px-2 py-1 border border-slate-200 rounded-md bg-white" />
+                    <button onClick={exportHistory} className="px-3 py-1 bg-chinese-indigo text-white rounded-md hover:opacity-90 transition-opacity">
+                        Export
+                    </button>
+                </div>
+            )}
+
             <div className="flex-1 overflow-y-auto p-3 space-y-2 custom-scrollbar" ref={scrollRef}>
                 {items.length === 0 ? (
                     <div className="flex flex-col items-center justify-center h-48 text-slate-400">
@@ -50,14 +132,33 @@ export function HistoryList() {
                             style={{ animationDelay: `${index * 30}ms` }}
                         >
                             <div className="flex justify-between items-center gap-2">
-                                <p className="text-slate-800 text-sm leading-snug flex-1 line-clamp-2">{item.text}</p>
+                                <p className="text-slate-800 text-sm leading-snug flex-1 line-clamp-2" title={item.edited_from ? `Originally: ${item.edited_from}` : undefined}>{item.text}</p>
                                 <div className="flex items-center gap-1 shrink-0">
                                     <span className="text-xs text-slate-400 hidden group-hover:inline">{item.timestamp.split(' ')[1]}</span>
-                                    <button onClick={() => copyText(item.text)} className="opacity-0 group-hover:opacity-100 p-1 hover:text-chinese-indigo transition-opacity">
+                                    <button onClick={() => copyText(item.text)} title="Copy" className="opacity-0 group-hover:opacity-100 p-1 hover:text-chinese-indigo transition-opacity">
                                         <Copy className="w-3 h-3" />
                                     </button>
+                                    <button onClick={() => editText(item)} title="Edit" className="opacity-0 group-hover:opacity-100 p-1 hover:text-chinese-indigo transition-opacity">
+                                        <Pencil className="w-3 h-3" />
+                                    </button>
+                                    <button onClick={() => editTags(item)} title="Tags" className="opacity-0 group-hover:opacity-100 p-1 hover:text-chinese-indigo transition-opacity">
+                                        <Tag className="w-3 h-3" />
+                                    </button>
+                                    <button onClick={() => api.setHistoryItemPinned(item.id, !item.pinned)} title={item.pinned ? "Unpin" : "Pin"} className={`p-1 hover:text-chinese-indigo transition-opacity ${item.pinned ? "text-chinese-indigo" : "opacity-0 group-hover:opacity-100"}`}>
+                                        <Pin className="w-3 h-3" />
+                                    </button>
+                                    <button onClick={() => api.deleteHistoryItem(item.id)} title="Delete" className="opacity-0 group-hover:opacity-100 p-1 hover:text-red-500 transition-opacity">
+                                        <X className="w-3 h-3" />
+                                    </button>
                                 </div>
                             </div>
+                            {item.tags.length > 0 && (
+                                <div className="flex flex-wrap gap-1 mt-1">
+                                    {item.tags.map(tag => (
+                                        <span key={tag} className="text-[10px] px-1.5 py-0.5 rounded bg-slate-100 text-slate-500">{tag}</span>
+                                    ))}
+                                </div>
+                            )}
                         </div>
                     ))
                 )}
@@ -1,7 +1,7 @@
-import { useEffect, useState } from "react";
-import { FolderOpen, Check, Loader2, Mic, X, Monitor, Keyboard, Languages, Sparkles, ChevronDown, ChevronUp, Globe, AlertCircle, Info, Upload } from "lucide-react";
-import { open } from "@tauri-apps/plugin-dialog";
-import { api, AppConfig, ModelVersion, ModelVersionsStatus, AudioDevice, LlmConfig, ProxyConfig, events } from "../lib/api";
+import { useEffect, useRef, useState } from "react";
+import { FolderOpen, Check, Loader2, Mic, X, Monitor, Keyboard, Languages, Sparkles, ChevronDown, ChevronUp, Globe, AlertCircle, Info, Upload, History, EyeOff, Layers, Download, Trash2 } from "lucide-react";
+import { open, save } from "@tauri-apps/plugin-dialog";
+import { api, AppConfig, ModelVersion, ModelVersionsStatus, AudioDevice, LlmConfig, ProxyConfig, HistoryConfig, ConfigPatch, DownloadProgress, ModelUpdateInfo, events, isStoredSecret } from "../lib/api";
 
 interface SettingsModalProps {
     isOpen: boolean;
@@ -14,10 +14,12 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
     const [versionsStatus, setVersionsStatus] = useState<ModelVersionsStatus | null>(null);
     const [inputDevices, setInputDevices] = useState<AudioDevice[]>([]);
     const [currentDevice, setCurrentDevice] = useState("");
+    // Settings changes sent but not yet confirmed by the backend
+    const pendingSaves = useRef(0);
 
     // Loading states
     const [downloading, setDownloading] = useState<ModelVersion | null>(null);
-    const [downloadProgress, setDownloadProgress] = useState<{ current: number; total: number } | null>(null);
+    const [downloadProgress, setDownloadProgress] = useState<DownloadProgress | null>(null);
     const [switchingDevice, setSwitchingDevice] = useState(false);
     const [switchingModel, setSwitchingModel] = useState(false);
 
@@ -30,6 +32,10 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
     const [llmTestResult, setLlmTestResult] = useState<{ success: boolean; message: string } | null>(null);
     const [showPromptEditor, setShowPromptEditor] = useState(false);
     const [defaultPrompt, setDefaultPrompt] = useState("");
+    // API key being typed; it is only saved (and moved to the keyring) when the field loses focus
+    const [apiKeyDraft, setApiKeyDraft] = useState("");
+    // Models offered by the endpoint, suggested for the model field
+    const [llmModels, setLlmModels] = useState<string[]>([]);
 
     // Download error state
     const [downloadError, setDownloadError] = useState<string | null>(null);
@@ -37,6 +43,13 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
     // Import state
     const [importing, setImporting] = useState(false);
 
+    // Newer model builds announced by the background update check
+    const [modelUpdates, setModelUpdates] = useState<ModelUpdateInfo[]>([]);
+
+    // Profiles and settings transfer state
+    const [newProfileName, setNewProfileName] = useState("");
+    const [exportSecrets, setExportSecrets] = useState(false);
+
     // Close warning state
     const [showCloseWarning, setShowCloseWarning] = useState(false);
 
@@ -49,9 +62,50 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
             api.getDefaultLlmPrompt().then(setDefaultPrompt);
             // Reset LLM test result when opening
             setLlmTestResult(null);
+            setApiKeyDraft("");
         }
     }, [isOpen]);
 
+    // Pick up settings changed elsewhere (model downloads, device switches, the profile hotkey).
+    // While our own changes are in flight the local state is newer than the backend's.
+    useEffect(() => {
+        if (!isOpen) return;
+        const unsubs = [
+            events.onConfigChanged(() => {
+                if (pendingSaves.current === 0) api.getConfig().then(setConfig);
+            }),
+            events.onProfileSwitched(() => {
+                api.getCurrentInputDevice().then(setCurrentDevice);
+            }),
+        ];
+        return () => { unsubs.forEach(u => u.then(f => f())); };
+    }, [isOpen]);
+
+    // Update checks run in the background, so keep listening while the modal is closed
+    useEffect(() => {
+        const unsub = events.onModelUpdateAvailable((update) => {
+            setModelUpdates(prev => [...prev.filter(u => u.version !== update.version), update]);
+        });
+        return () => { unsub.then(f => f()); };
+    }, []);
+
+    // Suggest the endpoint's models; endpoints that can't list them leave the field free text.
+    // Waits for typing in the base URL to pause before asking.
+    const llmConfig = config?.llm_config;
+    useEffect(() => {
+        if (!isOpen || !config || !llmConfig?.enabled || !llmConfig.base_url) {
+            setLlmModels([]);
+            return;
+        }
+        let cancelled = false;
+        const timer = setTimeout(() => {
+            api.listLlmModels(llmConfig, config.proxy)
+                .then(models => { if (!cancelled) setLlmModels(models); })
+                .catch(() => { if (!cancelled) setLlmModels([]); });
+        }, 500);
+        return () => { cancelled = true; clearTimeout(timer); };
+    }, [isOpen, llmConfig?.enabled, llmConfig?.provider, llmConfig?.base_url, llmConfig?.api_key, llmConfig?.api_version]);
+
     useEffect(() => {
         // Only set up download event listeners when modal is open
         if (!isOpen) return;
@@ -112,29 +166,109 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
         };
     }, [isOpen]);
 
+    // Send only the changed settings, so changes the backend made meanwhile aren't overwritten
+    const savePatch = async (patch: ConfigPatch) => {
+        pendingSaves.current += 1;
+        try {
+            return await api.patchConfig(patch);
+        } catch (e) {
+            alert(`Saving settings failed: ${e}`);
+        } finally {
+            pendingSaves.current -= 1;
+        }
+    };
+
     const updateConfig = (key: keyof AppConfig, value: any) => {
         if (!config) return;
-        const newConfig = { ...config, [key]: value };
-        setConfig(newConfig);
-        api.saveConfig(newConfig);
+        setConfig({ ...config, [key]: value });
+        savePatch({ [key]: value });
     };
 
     const updateLlmConfig = (key: keyof LlmConfig, value: any) => {
         if (!config) return;
-        const newLlmConfig = { ...config.llm_config, [key]: value };
-        const newConfig = { ...config, llm_config: newLlmConfig };
-        setConfig(newConfig);
-        api.saveConfig(newConfig);
+        setConfig({ ...config, llm_config: { ...config.llm_config, [key]: value } });
+        savePatch({ llm_config: { [key]: value } });
         // Clear test result when config changes
         setLlmTestResult(null);
     };
 
+    // An empty key removes the stored secret
+    const saveApiKey = async (apiKey: string) => {
+        if (!config) return;
+        const saved = await savePatch({ llm_config: { api_key: apiKey } });
+        setApiKeyDraft("");
+        // Keep only the keyring reference in the webview
+        if (saved) setConfig(c => c && { ...c, llm_config: { ...c.llm_config, api_key: saved.llm_config.api_key } });
+        setLlmTestResult(null);
+    };
+
     const updateProxyConfig = (key: keyof ProxyConfig, value: any) => {
         if (!config) return;
-        const newProxyConfig = { ...config.proxy, [key]: value };
-        const newConfig = { ...config, proxy: newProxyConfig };
-        setConfig(newConfig);
-        api.saveConfig(newConfig);
+        setConfig({ ...config, proxy: { ...config.proxy, [key]: value } });
+        savePatch({ proxy: { [key]: value } });
+    };
+
+    const reloadConfig = async () => {
+        setConfig(await api.getConfig());
+        setCurrentDevice(await api.getCurrentInputDevice());
+    };
+
+    const handleSaveProfile = async () => {
+        const name = newProfileName.trim();
+        if (!name) return;
+        if (config?.profiles.some(p => p.name === name) && !confirm(`Replace profile "${name}" with the current settings?`)) return;
+        try {
+            await api.saveProfile(name);
+            setNewProfileName("");
+            await reloadConfig();
+        } catch (e) {
+            alert(`Saving profile failed: ${e}`);
+        }
+    };
+
+    const handleSwitchProfile = async (name: string) => {
+        try {
+            await api.switchProfile(name);
+            await reloadConfig();
+        } catch (e) {
+            alert(`Switching profile failed: ${e}`);
+        }
+    };
+
+    const handleDeleteProfile = async (name: string) => {
+        if (!confirm(`Delete profile "${name}"?`)) return;
+        await api.deleteProfile(name).catch(e => alert(`Deleting profile failed: ${e}`));
+        await reloadConfig();
+    };
+
+    const handleExportConfig = async () => {
+        const path = await save({
+            defaultPath: "fastsp-settings.json",
+            filters: [{ name: "Settings", extensions: ["json"] }],
+        });
+        if (!path) return;
+        await api.exportConfig(path, exportSecrets).catch(e => alert(`Export failed: ${e}`));
+    };
+
+    const handleImportConfig = async () => {
+        const path = await open({
+            multiple: false,
+            filters: [{ name: "Settings", extensions: ["json"] }],
+        });
+        if (!path || typeof path !== "string") return;
+        if (!confirm("Replace all settings with the imported ones?")) return;
+        try {
+            setConfig(await api.importConfig(path));
+            setCurrentDevice(await api.getCurrentInputDevice());
+        } catch (e) {
+            alert(`Import failed: ${e}`);
+        }
+    };
+
+    const updateHistoryConfig = (key: keyof HistoryConfig, value: any) => {
+        if (!config) return;
+        setConfig({ ...config, history: { ...config.history, [key]: value } });
+        savePatch({ history: { [key]: value } });
     };
 
     const handleTestLlm = async () => {
@@ -143,7 +277,13 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
         setLlmTestResult(null);
         try {
             const result = await api.testLlmConnection(config.llm_config, config.proxy);
-            setLlmTestResult({ success: true, message: result });
+            const json = result.json_output_ok
+                ? `JSON output OK: ${result.json_output_detail}`
+                : `JSON output failed: ${result.json_output_detail}`;
+            setLlmTestResult({
+                success: result.json_output_ok,
+                message: `Connected to ${result.resolved_model} in ${result.latency_ms} ms. ${json}`,
+            });
         } catch (e: any) {
             setLlmTestResult({ success: false, message: e.toString() });
         } finally {
@@ -160,9 +300,10 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
         }
         setSwitchingDevice(true);
         try {
+            // Saves the device in the config as well
             await api.switchInputDevice(deviceName);
             setCurrentDevice(deviceName);
-            if (config) updateConfig("input_device", deviceName);
+            if (config) setConfig({ ...config, input_device: deviceName });
         } finally {
             setSwitchingDevice(false);
         }
@@ -224,15 +365,36 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
         }
     };
 
-    const handleImportModel = async () => {
+    const handleDownloadModelUpdate = async (update: ModelUpdateInfo) => {
+        if (isOperationInProgress) return;
+        setModelUpdates(prev => prev.filter(u => u !== update));
+        setDownloading(update.version);
+        setDownloadError(null);
+        try {
+            await api.downloadModelUpdate(update.version);
+        } catch (e) {
+            setDownloading(null);
+            setDownloadError(String(e));
+        }
+    };
+
+    const handleDismissModelUpdate = async (update: ModelUpdateInfo) => {
+        setModelUpdates(prev => prev.filter(u => u !== update));
+        await api.dismissModelUpdate(update.latest.name);
+    };
+
+    // Import an archive, or a folder of loose model files (e.g. a Hugging Face checkout)
+    const handleImportModel = async (directory = false) => {
         // Block if any operation is in progress
         if (isOperationInProgress) return;
 
         try {
-            const selected = await open({
-                multiple: false,
-                filters: [{ name: "Model Archive", extensions: ["bz2", "tar.bz2"] }],
-            });
+            const selected = await open(directory
+                ? { directory: true, multiple: false }
+                : {
+                    multiple: false,
+                    filters: [{ name: "Model Archive", extensions: ["bz2", "tar.bz2", "gz", "tgz", "xz", "zip"] }],
+                });
 
             if (selected && typeof selected === "string") {
                 setDownloadError(null);
@@ -330,6 +492,37 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
                         </div>
                     )}
 
+                    {/* Model Update Notices */}
+                    {modelUpdates.map(update => (
+                        <div key={update.version} className="bg-chinese-indigo/5 border border-chinese-indigo/20 rounded-xl p-4 animate-in slide-in-from-top-2 duration-200">
+                            <div className="flex items-start gap-3">
+                                <Download className="w-5 h-5 text-chinese-indigo flex-shrink-0 mt-0.5" />
+                                <div className="flex-1">
+                                    <h3 className="font-semibold text-slate-800 mb-1">
+                                        {update.version === "quantized" ? "Quantized" : "Unquantized"} model update available
+                                    </h3>
+                                    <p className="text-sm text-slate-600">{update.current_build} → {update.latest.name}</p>
+                                    {update.changelog && <p className="text-xs text-slate-500 mt-1 whitespace-pre-line">{update.changelog}</p>}
+                                    <div className="flex gap-3 mt-2">
+                                        <button
+                                            onClick={() => handleDownloadModelUpdate(update)}
+                                            disabled={isOperationInProgress}
+                                            className="text-xs text-chinese-indigo hover:underline disabled:opacity-50 disabled:cursor-not-allowed"
+                                        >
+                                            Download
+                                        </button>
+                                        <button
+                                            onClick={() => handleDismissModelUpdate(update)}
+                                            className="text-xs text-slate-500 hover:text-slate-700 underline"
+                                        >
+                                            Dismiss
+                                        </button>
+                                    </div>
+                                </div>
+                            </div>
+                        </div>
+                    ))}
+
                     {/* Triggers Section */}
                     <section>
                         <SectionHeader icon={Keyboard} title="Triggers" />
@@ -461,10 +654,17 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
                             <ImportCard
                                 importing={importing}
                                 disabled={isOperationInProgress && !importing}
-                                onClick={handleImportModel}
+                                onClick={() => handleImportModel()}
                             />
                         </div>
-                        <div className="mt-4 flex justify-end">
+                        <div className="mt-4 flex justify-end gap-4">
+                            <button
+                                onClick={() => handleImportModel(true)}
+                                disabled={isOperationInProgress}
+                                className="text-xs text-slate-400 hover:text-chinese-indigo flex items-center gap-1 transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+                            >
+                                <Upload className="w-3 h-3" /> Import from Folder
+                            </button>
                             <button onClick={api.openModelFolder} className="text-xs text-slate-400 hover:text-chinese-indigo flex items-center gap-1 transition-colors">
                                 <FolderOpen className="w-3 h-3" /> Open Model Folder
                             </button>
@@ -512,13 +712,25 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
                                     {/* API Key */}
                                     <div className="bg-slate-50 p-4 rounded-xl border border-slate-200">
                                         <label className="block text-sm font-medium text-slate-700 mb-2">API Key</label>
-                                        <input
-                                            type="password"
-                                            value={config.llm_config.api_key}
-                                            onChange={(e) => updateLlmConfig("api_key", e.target.value)}
-                                            placeholder="sk-..."
-                                            className="w-full bg-white border border-slate-200 rounded-lg px-3 py-2 text-sm text-slate-700 focus:ring-2 focus:ring-chinese-indigo outline-none"
-                                        />
+                                        <div className="flex gap-2">
+                                            <input
+                                                type="password"
+                                                value={apiKeyDraft}
+                                                onChange={(e) => setApiKeyDraft(e.target.value)}
+                                                onBlur={() => apiKeyDraft && saveApiKey(apiKeyDraft)}
+                                                onKeyDown={(e) => { if (e.key === "Enter") e.currentTarget.blur(); }}
+                                                placeholder={isStoredSecret(config.llm_config.api_key) ? "Stored in system keyring (type to replace)" : "sk-..."}
+                                                className="w-full bg-white border border-slate-200 rounded-lg px-3 py-2 text-sm text-slate-700 focus:ring-2 focus:ring-chinese-indigo outline-none"
+                                            />
+                                            {config.llm_config.api_key && (
+                                                <button
+                                                    onClick={() => saveApiKey("")}
+                                                    className="px-3 py-2 text-sm text-slate-500 hover:text-red-500 transition-colors whitespace-nowrap"
+                                                >
+                                                    Remove
+                                                </button>
+                                            )}
+                                        </div>
                                     </div>
 
                                     {/* Model */}
@@ -526,11 +738,26 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
                                         <label className="block text-sm font-medium text-slate-700 mb-2">Model</label>
                                         <input
                                             type="text"
+                                            list="llm-models"
                                             value={config.llm_config.model}
                                             onChange={(e) => updateLlmConfig("model", e.target.value)}
                                             placeholder="gpt-4o-mini"
                                             className="w-full bg-white border border-slate-200 rounded-lg px-3 py-2 text-sm text-slate-700 focus:ring-2 focus:ring-chinese-indigo outline-none"
                                         />
+                                        <datalist id="llm-models">
+                                            {llmModels.map(model => <option key={model} value={model} />)}
+                                        </datalist>
+                                        <label className="flex items-center gap-2 text-sm text-slate-700 mt-3">
+                                            <input
+                                                type="checkbox"
+                                                checked={config.llm_config.structured_output}
+                                                onChange={(e) => updateLlmConfig("structured_output", e.target.checked)}
+                                            />
+                                            Structured output (JSON schema)
+                                        </label>
+                                        <p className="text-xs text-slate-400 mt-1">
+                                            Turn off for OpenAI-compatible servers that reject response_format.
+                                        </p>
                                     </div>
 
                                     {/* Custom Prompt (Collapsible) */}
@@ -570,7 +797,7 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
                                         </button>
                                         {llmTestResult && (
                                             <span className={`text-sm ${llmTestResult.success ? "text-green-600" : "text-red-600"}`}>
-                                                {llmTestResult.success ? "Success!" : llmTestResult.message.slice(0, 50)}
+                                                {llmTestResult.success ? llmTestResult.message : llmTestResult.message.slice(0, 50)}
                                             </span>
                                         )}
                                     </div>
@@ -579,6 +806,146 @@ export function SettingsModal({ isOpen, onClose, isFirstSetup = false }: Setting
                         </div>
                     </section>
 
+                    {/* Profiles and settings transfer */}
+                    <section>
+                        <SectionHeader icon={Layers} title="Profiles" />
+                        <div className="space-y-4">
+                            <div className="bg-slate-50 p-4 rounded-xl border border-slate-200 space-y-3">
+                                {config?.profiles.length ? (
+                                    <div className="space-y-2">
+                                        {config.profiles.map(profile => (
+                                            <div key={profile.name} className="flex items-center justify-between bg-white px-3 py-2 rounded-lg border border-slate-200">
+                                                <div className="flex items-center gap-2">
+                                                    <span className="text-sm font-medium text-slate-800">{profile.name}</span>
+                                                    {config.active_profile === profile.name && (
+                                                        <span className="text-xs px-2 py-0.5 rounded-full bg-chinese-indigo/10 text-chinese-indigo">Active</span>
+                                                    )}
+                                                </div>
+                                                <div className="flex items-center gap-1">
+                                                    <button
+                                                        onClick={() => handleSwitchProfile(profile.name)}
+                                                        className="px-3 py-1 text-xs rounded-md text-chinese-indigo hover:bg-chinese-indigo/10 transition-colors"
+                                                    >
+                                                        Switch
+                                                    </button>
+                                                    <button
+                                                        onClick={() => handleDeleteProfile(profile.name)}
+                                                        className="p-1 text-slate-400 hover:text-red-500 transition-colors"
+                                                        title="Delete"
+                                                    >
+                                                        <Trash2 className="w-4 h-4" />
+                                                    </button>
+                                                </div>
+                                            </div>
+                                        ))}
+                                    </div>
+                                ) : (
+                                    <p className="text-xs text-slate-400">No profiles yet.</p>
+                                )}
+                                <div className="flex gap-2">
+                                    <input
+                                        type="text"
+                                        value={newProfileName}
+                                        onChange={(e) => setNewProfileName(e.target.value)}
+                                        placeholder="Profile name, e.g. office"
+                                        className="flex-1 bg-white border border-slate-200 rounded-lg px-3 py-2 text-sm text-slate-700 focus:ring-2 focus:ring-chinese-indigo outline-none"
+                                    />
+                                    <button
+                                        onClick={handleSaveProfile}
+                                        disabled={!newProfileName.trim()}
+                                        className="px-3 py-2 text-sm rounded-lg bg-chinese-indigo text-white disabled:opacity-50 hover:opacity-90 transition-opacity"
+                                    >
+                                        Save current
+                                    </button>
+                                </div>
+                                <p className="text-xs text-slate-400">
+                                    A profile holds the input device, model, language, triggers, LLM and proxy settings. Press Left Ctrl + Left Alt + P to switch to the next one.
+                                </p>
+                            </div>
+
+                            <div className="bg-slate-50 p-4 rounded-xl border border-slate-200 space-y-3">
+                                <div className="flex gap-2">
+                                    <button
+                                        onClick={handleExportConfig}
+                                        className="flex-1 flex items-center justify-center gap-2 px-3 py-2 text-sm rounded-lg bg-white border border-slate-200 text-slate-700 hover:bg-slate-100 transition-colors"
+                                    >
+                                        <Download className="w-4 h-4" /> Export settings
+                                    </button>
+                                    <button
+                                        onClick={handleImportConfig}
+                                        className="flex-1 flex items-center justify-center gap-2 px-3 py-2 text-sm rounded-lg bg-white border border-slate-200 text-slate-700 hover:bg-slate-100 transition-colors"
+                                    >
+                                        <Upload className="w-4 h-4" /> Import settings
+                                    </button>
+                                </div>
+                                <label className="flex items-center gap-2 text-sm text-slate-700">
+                                    <input type="checkbox" checked={exportSecrets} onChange={(e) => setExportSecrets(e.target.checked)} />
+                                    Include API keys in the export (written in plain text)
+                                </label>
+                            </div>
+                        </div>
+                    </section>
+
+                    {/* History retention */}
+                    <section>
+                        <SectionHeader icon={History} title="History" />
+                        <div className="space-y-4">
+                            <div className="bg-slate-50 p-4 rounded-xl border border-slate-200">
+                                <div className="flex items-center justify-between">
+                                    <div className="flex items-center gap-3">
+                                        <EyeOff className="w-5 h-5 text-slate-400" />
+                                        <div>
+                                            <div className="font-medium text-slate-800">Incognito Mode</div>
+                                            <div className="text-xs text-slate-500">Don't save dictations to history</div>
+                                        </div>
+                                    </div>
+                                    <button
+                                        onClick={() => updateHistoryConfig("incognito", !config?.history.incognito)}
+                                        className={`relative w-12 h-6 rounded-full transition-colors ${config?.history.incognito ? "bg-chinese-indigo" : "bg-slate-300"}`}
+                                    >
+                                        <div className={`absolute top-1 w-4 h-4 rounded-full bg-white shadow transition-all ${config?.history.incognito ? "left-7" : "left-1"}`} />
+                                    </button>
+                                </div>
+                            </div>
+
+                            <div className="bg-slate-50 p-4 rounded-xl border border-slate-200 space-y-3">
+                                <div className="grid grid-cols-2 gap-3">
+                                    <div>
+                                        <label className="block text-sm font-medium text-slate-700 mb-2">Keep at most (items)</label>
+                                        <input
+                                            type="number"
+                                            min={0}
+                                            value={config?.history.max_items ?? 0}
+                                            onChange={(e) => updateHistoryConfig("max_items", Math.max(0, parseInt(e.target.value) || 0))}
+                                            className="w-full bg-white border border-slate-200 rounded-lg px-3 py-2 text-sm text-slate-700 focus:ring-2 focus:ring-chinese-indigo outline-none"
+                                        />
+                                    </div>
+                                    <div>
+                                        <label className="block text-sm font-medium text-slate-700 mb-2">Delete after (days)</label>
+                                        <input
+                                            type="number"
+                                            min={0}
+                                            value={config?.history.max_age_days ?? 0}
+                                            onChange={(e) => updateHistoryConfig("max_age_days", Math.max(0, parseInt(e.target.value) || 0))}
+                                            className="w-full bg-white border border-slate-200 rounded-lg px-3 py-2 text-sm text-slate-700 focus:ring-2 focus:ring-chinese-indigo outline-none"
+                                        />
+                                    </div>
+                                </div>
+                                <label className="flex items-center gap-2 text-sm text-slate-700">
+                                    <input
+                                        type="checkbox"
+                                        checked={config?.history.keep_pinned ?? true}
+                                        onChange={(e) => updateHistoryConfig("keep_pinned", e.target.checked)}
+                                    />
+                                    Always keep pinned items
+                                </label>
+                                <p className="text-xs text-slate-400">
+                                    0 means no limit. Limits apply at startup and whenever a dictation is saved.
+                                </p>
+                            </div>
+                        </div>
+                    </section>
+
                     {/* Network / Proxy */}
                     <section>
                         <SectionHeader icon={Globe} title="Network" />
@@ -697,8 +1064,44 @@ function TriggerToggle({ label, desc, active, onClick }: any) {
     )
 }
 
+function formatBytes(bytes: number) {
+    if (bytes >= 1024 * 1024) return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+    return `${Math.round(bytes / 1024)} KB`;
+}
+
+function formatDuration(secs: number) {
+    if (secs >= 60) return `${Math.floor(secs / 60)}m ${secs % 60}s`;
+    return `${secs}s`;
+}
+
+// What the model card shows for each phase of a download
+function describeProgress(progress: DownloadProgress | null) {
+    if (!progress) return { label: "Connecting...", detail: "", source: "" };
+    switch (progress.phase) {
+        case "downloading": {
+            const parts = [`${formatBytes(progress.current)}${progress.total > 0 ? ` / ${formatBytes(progress.total)}` : ""}`];
+            if (progress.bytes_per_sec > 0) parts.push(`${formatBytes(progress.bytes_per_sec)}/s`);
+            if (progress.eta_secs !== null) parts.push(`${formatDuration(progress.eta_secs)} left`);
+            return { label: "Downloading...", detail: parts.join(" · "), source: progress.mirror };
+        }
+        case "verifying":
+            return { label: "Verifying...", detail: formatBytes(progress.current), source: progress.mirror };
+        case "extracting":
+            return { label: "Extracting...", detail: `${progress.entries_extracted} files`, source: "" };
+        case "loading":
+            return { label: "Loading model...", detail: "", source: "" };
+        default:
+            return { label: "Connecting...", detail: "", source: progress.mirror };
+    }
+}
+
 function ModelCard({ title, size, active, downloaded, downloading, switching, progress, onClick, onCancel, disabled }: any) {
-    const progressPercent = progress && progress.total > 0 ? Math.round((progress.current / progress.total) * 100) : 0;
+    // Downloading and extracting each have their own progress; the other phases fill the bar
+    const phase = (progress as DownloadProgress | null)?.phase ?? "connecting";
+    const progressPercent = phase === "verifying" || phase === "loading"
+        ? 100
+        : progress && progress.total > 0 ? Math.round((progress.current / progress.total) * 100) : 0;
+    const { label, detail, source } = describeProgress(progress);
 
     return (
         <button
@@ -725,7 +1128,7 @@ function ModelCard({ title, size, active, downloaded, downloading, switching, pr
                     {downloading ? (
                         <>
                             <div className="w-full flex justify-between items-center text-xs text-chinese-indigo mb-1">
-                                <span>Downloading...</span>
+                                <span>{label}</span>
                                 <div className="flex items-center gap-2">
                                     <span>{progressPercent}%</span>
                                     <button
@@ -741,8 +1144,14 @@ function ModelCard({ title, size, active, downloaded, downloading, switching, pr
                                 </div>
                             </div>
                             <div className="w-full h-1 bg-slate-200 rounded-full overflow-hidden">
-                                <div className="h-full bg-chinese-indigo transition-all duration-300" style={{ width: `${progressPercent}%` }} />
+                                <div className={`h-full bg-chinese-indigo transition-all duration-300 ${phase === "loading" ? "animate-pulse" : ""}`} style={{ width: `${progressPercent}%` }} />
                             </div>
+                            {(detail || source) && (
+                                <div className="w-full flex justify-between text-[10px] text-slate-400 mt-1 gap-2">
+                                    <span className="truncate">{detail}</span>
+                                    {source && <span className="truncate">{source}</span>}
+                                </div>
+                            )}
                         </>
                     ) : (
                         <div className="flex items-center gap-2 text-chinese-indigo text-sm">
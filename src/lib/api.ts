@@ -14,17 +14,133 @@ export interface ModelDetailedStatus {
     loaded: boolean;
 }
 
+export interface ModelStorageInfo {
+    version: ModelVersion;
+    downloaded: boolean;
+    size_bytes: number;
+    last_used: string | null;
+}
+
+export interface ModelBuild {
+    name: string;
+    url: string;
+    size_bytes: number;
+}
+
+export interface ModelUpdateInfo {
+    version: ModelVersion;
+    current_build: string;
+    latest: ModelBuild;
+    changelog: string;
+}
+
+export type DownloadPhase = "connecting" | "downloading" | "verifying" | "extracting" | "loading";
+
+export interface DownloadProgress {
+    phase: DownloadPhase;
+    current: number;
+    total: number;
+    bytes_per_sec: number;
+    eta_secs: number | null;
+    mirror: string;
+    entries_extracted: number;
+}
+
 export interface AudioDevice {
     name: string;
     is_default: boolean;
 }
 
+export type LlmProvider = "openai" | "anthropic" | "azure_openai" | "ollama" | "gemini";
+
+export interface LlmEndpoint {
+    provider: LlmProvider;
+    base_url: string;
+    api_key: string;
+    model: string;
+    api_version: string;
+    timeout_secs: number;
+    structured_output: boolean;
+}
+
+export interface GuardrailConfig {
+    enabled: boolean;
+    max_edit_ratio: number;
+    min_length_ratio: number;
+    max_length_ratio: number;
+    reject_script_change: boolean;
+}
+
 export interface LlmConfig {
     enabled: boolean;
+    provider: LlmProvider;
     base_url: string;
     api_key: string;
     model: string;
     custom_prompt: string;
+    stream: boolean;
+    progressive_typing: boolean;
+    api_version: string;
+    timeout_secs: number;
+    max_retries: number;
+    circuit_breaker_cooldown_secs: number;
+    circuit_breaker_threshold: number; // Consecutive network errors, 429 or 5xx before an endpoint is skipped
+    fallback_endpoints: LlmEndpoint[];
+    guardrail: GuardrailConfig;
+    structured_output: boolean;
+    prompt_profiles: PromptProfile[];
+    trigger_modes: TriggerLlmModes;
+    translation: TranslationConfig;
+    context: LlmContextConfig;
+    model_prices: ModelPrice[];
+}
+
+export interface ModelPrice {
+    model: string;
+    prompt_per_million: number;
+    completion_per_million: number;
+}
+
+export interface LlmContextConfig {
+    include_history: boolean;
+    history_items: number;
+    history_window_secs: number;
+    glossary: string[];
+    max_context_tokens: number;
+}
+
+export interface TranslationConfig {
+    target_language: string;
+    correct_same_language: boolean;
+}
+
+export interface PromptProfile {
+    id: string;
+    name: string;
+    prompt: string;
+    apply_guardrail: boolean;
+}
+
+// "default" uses the main prompt, "off" types the raw transcription
+export type TriggerLlmMode = "default" | "off" | "translate" | { profile: string };
+
+export interface TriggerLlmModes {
+    mouse: TriggerLlmMode;
+    hold: TriggerLlmMode;
+    toggle: TriggerLlmMode;
+}
+
+export interface ConnectionTestResult {
+    latency_ms: number;
+    resolved_model: string;
+    response: string;
+    json_output_ok: boolean;
+    json_output_detail: string;
+}
+
+export interface LlmProviderCapabilities {
+    requires_api_key: boolean;
+    supports_json_schema: boolean;
 }
 
 export interface ProxyConfig {
@@ -32,7 +148,42 @@ export interface ProxyConfig {
     url: string;
 }
 
+export interface ModelMirror {
+    name: string;
+    url_template: string; // "{name}" = build name, "{file}" = archive file name
+    urls: Record<string, string>; // Full archive URLs by build name, override the template
+    enabled: boolean;
+}
+
+export interface HistoryConfig {
+    max_items: number; // 0 = unlimited
+    max_age_days: number; // 0 = unlimited
+    keep_pinned: boolean;
+    incognito: boolean;
+}
+
+export interface ModelUpdateConfig {
+    enabled: boolean;
+    interval_hours: number;
+    catalog_url: string;
+    dismissed_builds: string[]; // Builds not to be notified about again
+}
+
+// A named set of settings to switch between
+export interface ConfigProfile {
+    name: string;
+    input_device: string;
+    model_version: ModelVersion;
+    language: string;
+    trigger_mouse: boolean;
+    trigger_hold: boolean;
+    trigger_toggle: boolean;
+    llm_config: LlmConfig;
+    proxy: ProxyConfig;
+}
+
 export interface AppConfig {
+    config_version: number;
     trigger_mouse: boolean;
     trigger_hold: boolean;
     trigger_toggle: boolean;
@@ -42,6 +193,76 @@ export interface AppConfig {
     input_device: string;
     llm_config: LlmConfig;
     proxy: ProxyConfig;
+    model_mirrors: ModelMirror[];
+    model_update: ModelUpdateConfig;
+    history: HistoryConfig;
+    profiles: ConfigProfile[];
+    active_profile: string | null;
+}
+
+// Settings to change; nested settings only need the fields that change
+export type ConfigPatch = Partial<Omit<AppConfig, "llm_config" | "proxy" | "history">> & {
+    llm_config?: Partial<LlmConfig>;
+    proxy?: Partial<ProxyConfig>;
+    history?: Partial<HistoryConfig>;
+};
+
+export interface TokenUsage {
+    prompt_tokens: number;
+    completion_tokens: number;
+}
+
+// Inclusive "YYYY-MM-DD" dates; omitted ends are unbounded
+export interface HistoryRange {
+    from?: string;
+    to?: string;
+}
+
+export type ExportFormat = "markdown" | "csv" | "json" | "jsonl" | "text";
+
+export interface ImportSummary {
+    added: number;
+    skipped: number;
+    pruned: number; // Removed afterwards by the history retention settings
+}
+
+export interface ModelUsageStats {
+    model: string;
+    requests: number;
+    failures: number;
+    prompt_tokens: number;
+    completion_tokens: number;
+    estimated_cost: number | null;
+}
+
+export interface LlmUsageStats {
+    requests: number;
+    failures: number;
+    prompt_tokens: number;
+    completion_tokens: number;
+    unmetered_requests: number;
+    estimated_cost: number;
+    by_model: ModelUsageStats[];
+}
+
+export interface DayStats {
+    date: string;
+    items: number;
+    words: number;
+    characters: number;
+    speaking_ms: number;
+}
+
+export interface DictationStats {
+    items: number;
+    words: number;
+    characters: number;
+    speaking_ms: number;
+    average_asr_latency_ms: number | null;
+    llm_usage_rate: number; // 0..1
+    words_per_minute: number | null;
+    languages: { language: string; items: number }[];
+    by_day: DayStats[];
 }
 
 export interface HistoryItem {
@@ -49,13 +270,45 @@ export interface HistoryItem {
     timestamp: string;
     text: string;
     duration_ms: number;
+    asr_latency_ms: number;
+    llm_rejection: string | null;
+    language: string;
+    original_text: string | null;
+    translated_to: string | null;
+    llm_model: string | null;
+    llm_usage: TokenUsage | null;
+    llm_error: string | null;
+    edited_from: string | null;
+    pinned: boolean;
+    tags: string[];
 }
 
+// Config values with this prefix refer to a key kept in the OS keyring; the key itself never reaches the webview
+export const SECRET_REF_PREFIX = "keyring:";
+
+export const isStoredSecret = (value: string) => value.startsWith(SECRET_REF_PREFIX);
+
 export const api = {
     getConfig: () => invoke<AppConfig>("get_config"),
+    getConfigError: () => invoke<string | null>("get_config_error"),
     saveConfig: (config: AppConfig) => invoke("save_config", { config }),
+    patchConfig: (patch: ConfigPatch) => invoke<AppConfig>("patch_config", { patch }),
+    saveProfile: (name: string) => invoke("save_profile", { name }),
+    deleteProfile: (name: string) => invoke("delete_profile", { name }),
+    switchProfile: (name: string) => invoke("switch_profile", { name }),
+    exportConfig: (path: string, includeSecrets: boolean) => invoke("export_config", { path, includeSecrets }),
+    importConfig: (path: string) => invoke<AppConfig>("import_config", { path }),
     getHistory: () => invoke<HistoryItem[]>("get_history"),
     clearHistory: () => invoke("clear_history"),
+    deleteHistoryItem: (id: string) => invoke("delete_history_item", { id }),
+    editHistoryItem: (id: string, text: string) => invoke("edit_history_item", { id, text }),
+    setHistoryItemPinned: (id: string, pinned: boolean) => invoke("set_history_item_pinned", { id, pinned }),
+    setHistoryItemTags: (id: string, tags: string[]) => invoke("set_history_item_tags", { id, tags }),
+    getLlmUsageStats: (range?: HistoryRange) => invoke<LlmUsageStats>("get_llm_usage_stats", { range }),
+    getDictationStats: (range?: HistoryRange) => invoke<DictationStats>("get_dictation_stats", { range }),
+    exportHistory: (format: ExportFormat, path: string, range?: HistoryRange, search?: string) =>
+        invoke<number>("export_history", { format, range, search, path }),
+    importHistory: (path: string) => invoke<ImportSummary>("import_history", { path }),
     checkModelStatus: () => invoke<boolean>("check_model_status"),
     getDetailedStatus: () => invoke<ModelDetailedStatus>("get_model_detailed_status"),
     downloadModel: () => invoke("download_model"),
@@ -65,6 +318,11 @@ export const api = {
     switchModelVersion: (version: ModelVersion) => invoke("switch_model_version", { version }),
     cancelDownload: () => invoke("cancel_download"),
     importModel: (filePath: string, version: ModelVersion) => invoke("import_model", { filePath, version }),
+    deleteModel: (version: ModelVersion, force?: boolean) => invoke("delete_model", { version, force }),
+    getModelStorageInfo: () => invoke<ModelStorageInfo[]>("get_model_storage_info"),
+    checkModelUpdate: () => invoke<ModelUpdateInfo[]>("check_model_update"),
+    downloadModelUpdate: (version: ModelVersion) => invoke("download_model_update", { version }),
+    dismissModelUpdate: (build: string) => invoke("dismiss_model_update", { build }),
     // Audio device APIs
     getInputDevices: () => invoke<AudioDevice[]>("get_input_devices"),
     getCurrentInputDevice: () => invoke<string>("get_current_input_device"),
@@ -72,14 +330,16 @@ export const api = {
     startAudioTest: () => invoke("start_audio_test"),
     stopAudioTest: () => invoke("stop_audio_test"),
     // LLM APIs
-    testLlmConnection: (config: LlmConfig, proxy: ProxyConfig) => invoke<string>("test_llm_connection", { config, proxy }),
+    testLlmConnection: (config: LlmConfig, proxy: ProxyConfig) => invoke<ConnectionTestResult>("test_llm_connection", { config, proxy }),
+    listLlmModels: (config: LlmConfig, proxy: ProxyConfig) => invoke<string[]>("list_llm_models", { config, proxy }),
     getDefaultLlmPrompt: () => invoke<string>("get_default_llm_prompt"),
+    getLlmProviderCapabilities: (provider: LlmProvider) => invoke<LlmProviderCapabilities>("get_llm_provider_capabilities", { provider }),
 };
 
 export const events = {
     onTranscriptionUpdate: (callback: (payload: HistoryItem) => void) => listen<HistoryItem>("transcription_update", (e) => callback(e.payload)),
     onRecordingStatus: (callback: (isRecording: boolean) => void) => listen<boolean>("recording_status", (e) => callback(e.payload)),
-    onDownloadProgress: (callback: (payload: { current: number, total: number }) => void) => listen("download_progress", (e) => callback(e.payload as any)),
+    onDownloadProgress: (callback: (payload: DownloadProgress) => void) => listen<DownloadProgress>("download_progress", (e) => callback(e.payload)),
     onDownloadComplete: (callback: () => void) => listen("download_complete", callback),
     onDownloadError: (callback: (error: string) => void) => listen("download_error", (e) => callback(e.payload as string)),
     onDownloadCancelled: (callback: () => void) => listen("download_cancelled", callback),
@@ -87,8 +347,12 @@ export const events = {
     onImportComplete: (callback: () => void) => listen("import_complete", callback),
     onImportError: (callback: (error: string) => void) => listen("import_error", (e) => callback(e.payload as string)),
     onModelLoaded: (callback: () => void) => listen("model_loaded", callback),
+    onModelUpdateAvailable: (callback: (update: ModelUpdateInfo) => void) => listen<ModelUpdateInfo>("model_update_available", (e) => callback(e.payload)),
     onAudioLevel: (callback: (level: number) => void) => listen<number>("audio_level", (e) => callback(e.payload)),
     onLlmProcessing: (callback: (isProcessing: boolean) => void) => listen<boolean>("llm_processing", (e) => callback(e.payload)),
     onMousePosition: (callback: (pos: { x: number; y: number }) => void) => listen<{ x: number; y: number }>("mouse_position", (e) => callback(e.payload)),
+    onConfigChanged: (callback: () => void) => listen("config_changed", callback),
+    onProfileSwitched: (callback: (name: string) => void) => listen<string>("profile_switched", (e) => callback(e.payload)),
+    onHistoryChanged: (callback: () => void) => listen("history_changed", callback),
 };
 